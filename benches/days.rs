@@ -0,0 +1,65 @@
+use advent_2021::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::fs;
+
+macro_rules! bench_days {
+    ($($day:literal => $module:ident),* $(,)?) => {
+        fn days(c: &mut Criterion) {
+            $(
+                let raw = fs::read_to_string(concat!("input/2021/day", $day, ".txt"))
+                    .expect("unable to read input");
+                let input = $module::parse(&raw).expect("unable to parse input");
+
+                let mut group = c.benchmark_group(stringify!($module));
+                group.bench_function("generator", |b| b.iter(|| $module::parse(black_box(&raw))));
+                group.bench_function("part1", |b| b.iter(|| $module::part1(black_box(&input))));
+                group.bench_function("part2", |b| b.iter(|| $module::part2(black_box(&input))));
+                group.finish();
+            )*
+        }
+    };
+}
+
+bench_days! {
+    1 => day1,
+    2 => day2,
+    3 => day3,
+    4 => day4,
+    5 => day5,
+    6 => day6,
+    7 => day7,
+    8 => day8,
+    9 => day9,
+    10 => day10,
+    11 => day11,
+    12 => day12,
+    13 => day13,
+    14 => day14,
+    15 => day15,
+    16 => day16,
+    17 => day17,
+    18 => day18,
+    19 => day19,
+    20 => day20,
+    21 => day21,
+    22 => day22,
+}
+
+/// Day 19's generator both parses and aligns the scanners, so aligning is
+/// also measured on its own.
+fn day19_align(c: &mut Criterion) {
+    let raw = fs::read_to_string("input/2021/day19.txt").expect("unable to read input");
+    let reports = day19::parse_reports(&raw).expect("unable to parse input");
+    let config = day19::MatchConfig::default();
+
+    c.bench_function("day19/align", |b| {
+        b.iter(|| day19::align(black_box(&reports), &config))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = days, day19_align
+}
+criterion_main!(benches);
@@ -0,0 +1,145 @@
+//! The manifest of confirmed answers for the bundled puzzle inputs.
+//!
+//! The manifest is a tab separated file with one `day part answer` entry per
+//! line. Answers that span several lines are stored with `\n` escapes.
+
+use eyre::{bail, ContextCompat, WrapErr};
+use std::{collections::BTreeMap, fmt, fs, path::Path, str::FromStr};
+
+/// The default location of the manifest, relative to the crate root.
+pub const MANIFEST_PATH: &str = "input/2021/answers.tsv";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    answers: BTreeMap<(u8, u8), String>,
+}
+
+impl Manifest {
+    pub fn load<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .wrap_err_with(|| format!("unable to read {}", path.display()))?
+            .parse()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> eyre::Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string())
+            .wrap_err_with(|| format!("unable to write {}", path.display()))
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.answers.get(&(day, part)).map(String::as_str)
+    }
+
+    /// Adds a confirmed answer. Recording the same answer twice is allowed,
+    /// but an answer that disagrees with the one already recorded is an error.
+    pub fn record(&mut self, day: u8, part: u8, answer: &str) -> eyre::Result<()> {
+        match self.get(day, part) {
+            Some(existing) if existing != answer => bail!(
+                "day {} part {} is already recorded as {:?}",
+                day,
+                part,
+                existing
+            ),
+            _ => {
+                self.answers.insert((day, part), answer.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, u8, &str)> {
+        self.answers
+            .iter()
+            .map(|(&(day, part), answer)| (day, part, answer.as_str()))
+    }
+}
+
+fn escape(answer: &str) -> String {
+    answer.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(answer: &str) -> eyre::Result<String> {
+    let mut chars = answer.chars();
+    let mut buffer = String::with_capacity(answer.len());
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            buffer.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => buffer.push('\n'),
+            Some('\\') => buffer.push('\\'),
+            other => bail!("unknown escape {:?}", other),
+        }
+    }
+
+    Ok(buffer)
+}
+
+fn parse_entry(line: &str) -> eyre::Result<((u8, u8), String)> {
+    let mut fields = line.splitn(3, '\t');
+    let mut field = || fields.next().context("missing field");
+    let day = field()?.parse()?;
+    let part = field()?.parse()?;
+    let answer = unescape(field()?)?;
+
+    Ok(((day, part), answer))
+}
+
+impl FromStr for Manifest {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let answers = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(index, line)| {
+                parse_entry(line).wrap_err_with(|| format!("invalid manifest line {}", index + 1))
+            })
+            .collect::<eyre::Result<_>>()?;
+
+        Ok(Manifest { answers })
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (day, part, answer) in self.iter() {
+            writeln!(f, "{}\t{}\t{}", day, part, escape(answer))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() -> eyre::Result<()> {
+        let mut manifest = Manifest::default();
+        manifest.record(1, 1, "1154")?;
+        manifest.record(13, 2, "\n#.\\#\n")?;
+
+        let parsed: Manifest = manifest.to_string().parse()?;
+        assert_eq!(parsed, manifest);
+        assert_eq!(parsed.get(13, 2), Some("\n#.\\#\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_conflict() -> eyre::Result<()> {
+        let mut manifest = Manifest::default();
+        manifest.record(1, 1, "1154")?;
+        manifest.record(1, 1, "1154")?;
+        assert!(manifest.record(1, 1, "1155").is_err());
+
+        Ok(())
+    }
+}
@@ -1,23 +1,73 @@
-use std::num::ParseIntError;
+//! Day 1: Sonar Sweep
 
+use crate::error::{lines, ParseError};
+
+/// Depth readings from the sonar sweep, in order.
+pub type Input = Vec<u32>;
+
+/// Parses one depth reading per line.
 #[aoc_generator(day1)]
-fn generator(input: &str) -> Result<Vec<u32>, ParseIntError> {
-    input.lines().map(str::parse).collect()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    lines(1, input)
+        .map(|line| line.parse(line.text()))
+        .collect()
 }
 
+/// Counts the readings that are deeper than the one before.
 #[aoc(day1, part1)]
-fn part1(input: &[u32]) -> usize {
+pub fn part1(input: &[u32]) -> usize {
     input
         .windows(2)
         .filter(|readings| readings[1] > readings[0])
         .count()
 }
 
+/// Counts the increases between sums of a three reading sliding window.
 #[aoc(day1, part2)]
-fn part2(input: &[u32]) -> usize {
-    let sums: Vec<u32> = input
+pub fn part2(input: &[u32]) -> usize {
+    let sums: Vec<u64> = input
         .windows(3)
-        .map(|readings| readings.iter().sum())
+        .map(|readings| readings.iter().copied().map(u64::from).sum())
         .collect();
     sums.windows(2).filter(|parts| parts[1] > parts[0]).count()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "199
+200
+208
+210
+200
+207
+240
+269
+260
+263";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input, [199, 200, 208, 210, 200, 207, 240, 269, 260, 263]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), 5);
+
+        Ok(())
+    }
+}
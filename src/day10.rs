@@ -1,6 +1,24 @@
+//! Day 10: Syntax Scoring
+
+use crate::error::{lines, ParseError};
+use eyre::ContextCompat;
+
+/// The lines of the navigation subsystem.
+pub type Input = Vec<String>;
+
+/// Parses the navigation subsystem, one line of chunks per line.
 #[aoc_generator(day10)]
-fn generator(input: &str) -> Vec<String> {
-    input.lines().map(String::from).collect()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    lines(10, input)
+        .map(|line| {
+            let text = line.check_chars(
+                line.text(),
+                |c| "()[]{}<>".contains(c),
+                "expected a bracket",
+            )?;
+            Ok(text.to_string())
+        })
+        .collect()
 }
 
 fn check_syntax(line: &str) -> Result<Vec<char>, char> {
@@ -17,26 +35,25 @@ fn check_syntax(line: &str) -> Result<Vec<char>, char> {
     })
 }
 
+/// Sums the syntax error scores of the corrupted lines.
 #[aoc(day10, part1)]
-fn part1(input: &[String]) -> u64 {
+pub fn part1(input: &[String]) -> u64 {
     input
         .iter()
-        .filter_map(|line| match check_syntax(line) {
-            Ok(_) => None,
-            Err(c) => Some(c),
-        })
-        .map(|c| match c {
-            ')' => 3,
-            ']' => 57,
-            '}' => 1197,
-            '>' => 25137,
-            _ => unreachable!(),
+        .filter_map(|line| check_syntax(line).err())
+        .filter_map(|c| match c {
+            ')' => Some(3),
+            ']' => Some(57),
+            '}' => Some(1197),
+            '>' => Some(25137),
+            _ => None,
         })
         .sum()
 }
 
+/// Finds the middle completion score of the incomplete lines.
 #[aoc(day10, part2)]
-fn part2(input: &[String]) -> u64 {
+pub fn part2(input: &[String]) -> eyre::Result<u64> {
     let mut scores = input
         .iter()
         .filter_map(|line| check_syntax(line).ok())
@@ -44,17 +61,73 @@ fn part2(input: &[String]) -> u64 {
             stack
                 .iter()
                 .rev()
-                .map(|&c| match c {
-                    '(' => 1,
-                    '[' => 2,
-                    '{' => 3,
-                    '<' => 4,
-                    _ => unreachable!(),
+                .filter_map(|&c| match c {
+                    '(' => Some(1),
+                    '[' => Some(2),
+                    '{' => Some(3),
+                    '<' => Some(4),
+                    _ => None,
                 })
-                .fold(0, |acc, score| acc * 5 + score)
+                .try_fold(0u64, |acc, score| acc.checked_mul(5)?.checked_add(score))
+                .context("completion score overflowed")
         })
-        .collect::<Vec<_>>();
+        .collect::<eyre::Result<Vec<_>>>()?;
 
     scores.sort_unstable();
-    scores[scores.len() / 2]
+    scores
+        .get(scores.len() / 2)
+        .copied()
+        .context("no incomplete lines")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.len(), 10);
+        assert_eq!(input[9], "<{([{{}}[<[[[<>{}]]]>[]]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_syntax() {
+        assert_eq!(check_syntax("{([(<{}[<>[]}>{[]{[(<()>"), Err('}'));
+        assert_eq!(check_syntax("[[<[([]))<([[{}[[()]]]"), Err(')'));
+        assert_eq!(check_syntax("<{([([[(<>()){}]>(<<{{"), Err('>'));
+        assert_eq!(
+            check_syntax("[({(<(())[]>[[{[]{<()<>>").map(|s| s.len()),
+            Ok(8)
+        );
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), 26397);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input)?, 288957);
+
+        Ok(())
+    }
 }
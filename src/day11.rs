@@ -1,90 +1,234 @@
-use std::collections::{HashMap, HashSet};
+//! Day 11: Dumbo Octopus
 
-type Point = (i16, i16);
+use crate::{
+    error::{lines, ParseError},
+    grid::{Grid, Point},
+    params::define_params,
+};
+use std::collections::HashSet;
 
-#[aoc_generator(day11)]
-fn generator(input: &str) -> Option<HashMap<Point, u8>> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(x, c)| c.to_digit(10).map(|n| ((x as i16, y as i16), n as u8)))
-        })
-        .collect()
-}
+/// The energy level of each octopus.
+pub type Input = Grid<u8>;
 
-fn neighbors((x, y): Point) -> impl Iterator<Item = Point> {
-    [
-        (-1, -1),
-        (0, -1),
-        (1, -1),
-        (-1, 0),
-        (1, 0),
-        (-1, 1),
-        (0, 1),
-        (1, 1),
-    ]
-    .into_iter()
-    .map(move |(dx, dy)| (x + dx, y + dy))
+/// Parses the energy levels, one digit per octopus.
+#[aoc_generator(day11)]
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    Grid::parse_digits(lines(11, input))
 }
 
-fn step(state: &mut HashMap<Point, u8>) -> usize {
-    for (_, energy) in state.iter_mut() {
-        *energy += 1;
+/// Advances the octopuses by one step, returning how many flashed.
+pub fn step(state: &mut Grid<u8>) -> usize {
+    for energy in state.values_mut() {
+        *energy = energy.saturating_add(1);
     }
 
-    let mut flashed = HashSet::new();
+    let mut flashed = state.map(|_| false);
     let mut search = state
         .iter()
         .filter(|(_, &e)| e > 9)
         .map(|(p, _)| p)
-        .cloned()
         .collect::<Vec<Point>>();
     while let Some(point) = search.pop() {
-        if flashed.contains(&point) {
+        if flashed[point] {
             continue;
         }
 
-        flashed.insert(point);
+        flashed[point] = true;
 
-        for neighbor in neighbors(point) {
-            if let Some(energy) = state.get_mut(&neighbor) {
-                *energy += 1;
+        for neighbor in state.neighbors8(point) {
+            let energy = &mut state[neighbor];
+            *energy = energy.saturating_add(1);
 
-                if *energy > 9 && !flashed.contains(&neighbor) {
-                    search.push(neighbor);
-                }
+            if *energy > 9 && !flashed[neighbor] {
+                search.push(neighbor);
             }
         }
     }
 
-    for &point in &flashed {
-        state.insert(point, 0);
+    let mut count = 0;
+    for (point, &did_flash) in flashed.iter() {
+        if did_flash {
+            state[point] = 0;
+            count += 1;
+        }
     }
 
-    flashed.len()
+    count
 }
 
+define_params! {
+    /// How long the octopuses are watched for.
+    pub struct Params {
+        /// Steps counted by part 1.
+        pub steps: usize = 100,
+    }
+}
+
+/// Counts the flashes over the first 100 steps.
 #[aoc(day11, part1)]
-fn part1(input: &HashMap<Point, u8>) -> usize {
-    (0..100)
+pub fn part1(input: &Input) -> usize {
+    part1_with(input, &Params::default())
+}
+
+/// Counts the flashes over the first `steps` steps.
+pub fn part1_with(input: &Input, params: &Params) -> usize {
+    (0..params.steps)
         .scan(input.clone(), |state, _| Some(step(state)))
         .sum()
 }
 
+/// Finds the first step where every octopus flashes at once, or `None` if
+/// the octopuses fall into a cycle that never flashes together.
 #[aoc(day11, part2)]
-fn part2(input: &HashMap<Point, u8>) -> Option<usize> {
-    (1..)
-        .scan(input.clone(), |state, round| {
-            let flashed = step(state);
-            if flashed == state.len() {
-                None
-            } else {
-                Some(round)
-            }
-        })
-        .last()
-        .map(|round| round + 1)
+pub fn part2(input: &Input) -> Option<usize> {
+    let mut state = input.clone();
+    let mut seen = HashSet::new();
+
+    for round in 1.. {
+        if !seen.insert(state.clone()) {
+            return None;
+        }
+
+        if step(&mut state) == state.len() {
+            return Some(round);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+
+    fn grid(input: &str) -> eyre::Result<Input> {
+        Ok(parse(input)?)
+    }
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = grid(EXAMPLE)?;
+        assert_eq!(input.width(), 10);
+        assert_eq!(input.height(), 10);
+        assert_eq!(input[(0, 0)], 5);
+        assert_eq!(input[(9, 9)], 6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step_small() -> eyre::Result<()> {
+        let mut state = grid(
+            "11111
+19991
+19191
+19991
+11111",
+        )?;
+
+        assert_eq!(step(&mut state), 9);
+        assert_eq!(
+            state,
+            grid(
+                "34543
+40004
+50005
+40004
+34543"
+            )?
+        );
+
+        assert_eq!(step(&mut state), 0);
+        assert_eq!(
+            state,
+            grid(
+                "45654
+51115
+61116
+51115
+45654"
+            )?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step() -> eyre::Result<()> {
+        let mut state = grid(EXAMPLE)?;
+
+        assert_eq!(step(&mut state), 0);
+        assert_eq!(
+            state,
+            grid(
+                "6594254334
+3856965822
+6375667284
+7252447257
+7468496589
+5278635756
+3287952832
+7993992245
+5957959665
+6394862637"
+            )?
+        );
+
+        assert_eq!(step(&mut state), 35);
+        assert_eq!(
+            state,
+            grid(
+                "8807476555
+5089087054
+8597889608
+8485769600
+8700908800
+6600088989
+6800005943
+0000007456
+9000000876
+8700006848"
+            )?
+        );
+
+        let flashes = (2..10).map(|_| step(&mut state)).sum::<usize>();
+        assert_eq!(flashes, 204 - 35);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = grid(EXAMPLE)?;
+        assert_eq!(part1(&input), 1656);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = grid(EXAMPLE)?;
+        assert_eq!(part2(&input), Some(195));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_params() -> eyre::Result<()> {
+        let input = grid(EXAMPLE)?;
+        assert_eq!(part1_with(&input, &Params { steps: 10 }), 204);
+
+        Ok(())
+    }
 }
@@ -1,19 +1,28 @@
+//! Day 12: Passage Pathing
+
+use crate::error::{lines, ParseError};
 use std::collections::{HashMap, HashSet};
 
+/// The cave system as a map from each cave to the caves connected to it.
+pub type Input = HashMap<String, Vec<String>>;
+
+/// Parses one `start-end` connection per line.
 #[aoc_generator(day12)]
-fn generator(input: &str) -> HashMap<String, Vec<String>> {
-    input.lines().filter_map(|line| line.split_once("-")).fold(
-        HashMap::new(),
-        |mut acc, (start, end)| {
-            acc.entry(start.to_string())
-                .or_default()
-                .push(end.to_string());
-            acc.entry(end.to_string())
-                .or_default()
-                .push(start.to_string());
-            acc
-        },
-    )
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    lines(12, input).try_fold(HashMap::new(), |mut acc: Input, line| {
+        let (start, end) = line.split_once("-")?;
+        if is_big_cave(start) && is_big_cave(end) {
+            return Err(line.error(line.text(), "big caves can't be connected"));
+        }
+
+        acc.entry(start.to_string())
+            .or_default()
+            .push(end.to_string());
+        acc.entry(end.to_string())
+            .or_default()
+            .push(start.to_string());
+        Ok(acc)
+    })
 }
 
 #[derive(Default, Clone)]
@@ -31,7 +40,13 @@ fn is_small_cave(input: &str) -> bool {
     }
 }
 
-fn solve(input: &HashMap<String, Vec<String>>, small_double_visited: bool) -> usize {
+fn is_big_cave(input: &str) -> bool {
+    !matches!(input, "start" | "end") && !is_small_cave(input)
+}
+
+/// Counts the paths from `start` to `end`. Unless `small_double_visited` is
+/// set, a single small cave may be visited twice.
+pub fn solve(input: &Input, small_double_visited: bool) -> usize {
     let mut complete = HashSet::new();
     let mut search = vec![SearchEntry {
         cave: "start",
@@ -58,8 +73,10 @@ fn solve(input: &HashMap<String, Vec<String>>, small_double_visited: bool) -> us
                     || (is_small_cave(entry.cave) && entry.visited.contains(entry.cave));
                 entry.visited.insert(entry.cave);
 
-                let neightbors = input[entry.cave]
-                    .iter()
+                let neightbors = input
+                    .get(entry.cave)
+                    .into_iter()
+                    .flatten()
                     .filter(|&neighbor| neighbor != "start")
                     .map(|neighbor| SearchEntry {
                         cave: neighbor,
@@ -75,12 +92,91 @@ fn solve(input: &HashMap<String, Vec<String>>, small_double_visited: bool) -> us
     complete.len()
 }
 
+/// Counts the paths that visit small caves at most once.
 #[aoc(day12, part1)]
-fn part1(input: &HashMap<String, Vec<String>>) -> usize {
+pub fn part1(input: &Input) -> usize {
     solve(input, true)
 }
 
+/// Counts the paths that visit a single small cave at most twice.
 #[aoc(day12, part2)]
-fn part2(input: &HashMap<String, Vec<String>>) -> usize {
+pub fn part2(input: &Input) -> usize {
     solve(input, false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL_EXAMPLE: &str = "start-A
+start-b
+A-c
+A-b
+b-d
+A-end
+b-end";
+
+    const MEDIUM_EXAMPLE: &str = "dc-end
+HN-start
+start-kj
+dc-start
+dc-HN
+LN-dc
+HN-end
+kj-sa
+kj-HN
+kj-dc";
+
+    const LARGE_EXAMPLE: &str = "fs-end
+he-DX
+fs-he
+start-DX
+pj-DX
+end-zg
+zg-sl
+zg-pj
+pj-he
+RW-he
+fs-DX
+pj-RW
+zg-RW
+start-pj
+he-WI
+zg-he
+pj-fs
+start-RW";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(SMALL_EXAMPLE)?;
+        assert_eq!(input.len(), 6);
+
+        let mut start = input["start"].clone();
+        start.sort();
+        assert_eq!(start, ["A", "b"]);
+
+        let mut b = input["b"].clone();
+        b.sort();
+        assert_eq!(b, ["A", "d", "end", "start"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        assert_eq!(part1(&parse(SMALL_EXAMPLE)?), 10);
+        assert_eq!(part1(&parse(MEDIUM_EXAMPLE)?), 19);
+        assert_eq!(part1(&parse(LARGE_EXAMPLE)?), 226);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        assert_eq!(part2(&parse(SMALL_EXAMPLE)?), 36);
+        assert_eq!(part2(&parse(MEDIUM_EXAMPLE)?), 103);
+        assert_eq!(part2(&parse(LARGE_EXAMPLE)?), 3509);
+
+        Ok(())
+    }
+}
@@ -1,56 +1,77 @@
-use eyre::{bail, ContextCompat};
+//! Day 13: Transparent Origami
+
+use crate::error::{lines, Line, ParseError};
+use eyre::ContextCompat;
 use std::{collections::HashSet, fmt::Write};
 
-type Point = (i16, i16);
-type Paper = HashSet<Point>;
+pub type Point = (i16, i16);
+/// The set of dots on the transparent paper.
+pub type Paper = HashSet<Point>;
 
-#[derive(Debug, Clone, Copy)]
-enum Fold {
+/// A fold instruction along a horizontal or vertical line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fold {
     Up(i16),
     Left(i16),
 }
 
+/// The dots and the fold instructions.
 #[derive(Debug)]
-struct Input {
-    paper: Paper,
-    folds: Vec<Fold>,
+pub struct Input {
+    pub paper: Paper,
+    pub folds: Vec<Fold>,
 }
 
+fn coordinate(line: &Line, span: &str) -> Result<i16, ParseError> {
+    match line.parse(span)? {
+        value if value >= 0 => Ok(value),
+        _ => Err(line.error(span, "expected a non-negative coordinate")),
+    }
+}
+
+/// Parses the dot coordinates followed by the fold instructions.
 #[aoc_generator(day13)]
-fn generator(input: &str) -> eyre::Result<Input> {
-    let paper = input
-        .lines()
-        .take_while(|line| !line.is_empty())
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let mut lines = lines(13, input);
+    let paper = lines
+        .by_ref()
+        .take_while(|line| !line.text().is_empty())
         .map(|line| {
-            let (x, y) = line.split_once(',').context("unable to split point")?;
-            let x = x.parse()?;
-            let y = y.parse()?;
+            let (x, y) = line.split_once(",")?;
+            let x = coordinate(&line, x)?;
+            let y = coordinate(&line, y)?;
 
             Ok((x, y))
         })
-        .collect::<eyre::Result<Paper>>()?;
+        .collect::<Result<Paper, ParseError>>()?;
 
-    let folds = input
-        .lines()
-        .skip(paper.len() + 1)
+    let folds = lines
         .map(|line| {
-            let (instruction, position) = line.split_once('=').context("unable to split fold")?;
-            let position = position.parse()?;
+            let (instruction, position) = line.split_once("=")?;
+            let position = coordinate(&line, position)?;
 
             let fold = match instruction {
                 "fold along x" => Fold::Left(position),
                 "fold along y" => Fold::Up(position),
-                _ => bail!("bad fold"),
+                _ => return Err(line.error(instruction, "unknown fold")),
             };
 
             Ok(fold)
         })
-        .collect::<eyre::Result<Vec<Fold>>>()?;
+        .collect::<Result<Vec<Fold>, ParseError>>()?;
 
     Ok(Input { paper, folds })
 }
 
-fn solve(input: &Input) -> impl Iterator<Item = Paper> + '_ {
+/// Mirrors `value` across the fold line at `at`. Widening keeps the
+/// intermediate `2 * at` from overflowing.
+fn reflect(value: i16, at: i16) -> i16 {
+    let reflected = 2 * i32::from(at) - i32::from(value);
+    i16::try_from(reflected).unwrap_or(i16::MIN)
+}
+
+/// Applies each fold in turn, yielding the paper after every fold.
+pub fn solve(input: &Input) -> impl Iterator<Item = Paper> + '_ {
     input
         .folds
         .iter()
@@ -58,8 +79,8 @@ fn solve(input: &Input) -> impl Iterator<Item = Paper> + '_ {
             let next = paper
                 .iter()
                 .map(|&(x, y)| match fold {
-                    Fold::Left(fx) if fx < x => (-(x - fx - fx), y),
-                    Fold::Up(fy) if fy < y => (x, -(y - fy - fy)),
+                    Fold::Left(fx) if fx < x => (reflect(x, fx), y),
+                    Fold::Up(fy) if fy < y => (x, reflect(y, fy)),
                     _ => (x, y),
                 })
                 .collect::<Paper>();
@@ -69,16 +90,18 @@ fn solve(input: &Input) -> impl Iterator<Item = Paper> + '_ {
         })
 }
 
+/// Counts the dots visible after the first fold.
 #[aoc(day13, part1)]
-fn part1(input: &Input) -> eyre::Result<usize> {
+pub fn part1(input: &Input) -> eyre::Result<usize> {
     Ok(solve(input)
         .next()
         .context("unable to find solution")?
         .len())
 }
 
+/// Renders the paper after every fold, revealing the activation code.
 #[aoc(day13, part2)]
-fn part2(input: &Input) -> eyre::Result<String> {
+pub fn part2(input: &Input) -> eyre::Result<String> {
     let paper = solve(input).last().context("unable to find solution")?;
 
     let &(maxx, _) = paper
@@ -106,3 +129,99 @@ fn part2(input: &Input) -> eyre::Result<String> {
 
     Ok(buffer)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "6,10
+0,14
+9,10
+0,3
+10,4
+4,11
+6,0
+6,12
+4,1
+0,13
+10,12
+3,4
+3,0
+8,4
+1,10
+2,14
+8,10
+9,0
+
+fold along y=7
+fold along x=5";
+
+    fn paper(drawing: &str) -> Paper {
+        drawing
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| (x as i16, y as i16))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.paper.len(), 18);
+        assert!(input.paper.contains(&(6, 10)));
+        assert!(input.paper.contains(&(9, 0)));
+        assert_eq!(input.folds.len(), 2);
+        assert!(matches!(input.folds[0], Fold::Up(7)));
+        assert!(matches!(input.folds[1], Fold::Left(5)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let mut folds = solve(&input);
+
+        let expected = paper(
+            "#.##..#..#.
+#...#......
+......#...#
+#...#......
+.#.#..#.###",
+        );
+        assert_eq!(folds.next(), Some(expected));
+
+        let expected = paper(
+            "#####
+#...#
+#...#
+#...#
+#####",
+        );
+        assert_eq!(folds.next(), Some(expected));
+        assert_eq!(folds.next(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input)?, 17);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input)?, "\n#####\n#...#\n#...#\n#...#\n#####\n");
+
+        Ok(())
+    }
+}
@@ -1,37 +1,48 @@
+//! Day 14: Extended Polymerization
+
+use crate::{
+    error::{empty_input, lines, ParseError},
+    params::define_params,
+};
 use eyre::ContextCompat;
 use itertools::Itertools;
 use std::{collections::HashMap, iter};
 
-type Pair = (char, char);
+/// Two adjacent elements of a polymer.
+pub type Pair = (char, char);
 
+/// The polymer template and the pair insertion rules.
 #[derive(Debug)]
-struct Input {
-    template: Vec<char>,
-    rules: HashMap<Pair, char>,
+pub struct Input {
+    pub template: Vec<char>,
+    pub rules: HashMap<Pair, char>,
 }
 
+/// Parses the template followed by one `AB -> C` rule per line.
 #[aoc_generator(day14)]
-fn generator(input: &str) -> eyre::Result<Input> {
-    let template = input
-        .lines()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let mut lines = lines(14, input);
+    let template = lines
         .next()
-        .map(|line| line.chars().collect())
-        .context("unable to find template")?;
+        .map(|line| line.text().chars().collect())
+        .ok_or_else(|| empty_input(14))?;
 
-    let rules = input
-        .lines()
-        .skip(2)
+    let rules = lines
+        .skip(1)
         .map(|line| {
-            let (from, to) = line.split_once(" -> ").context("unable to split rule")?;
-            let from_parts = from.chars().collect::<Vec<char>>();
-            let from = (from_parts[0], from_parts[1]);
-            let to = to
-                .chars()
-                .next()
-                .context("unable to find second rule part")?;
+            let (from, to) = line.split_once(" -> ")?;
+            let from = match from.chars().collect::<Vec<char>>()[..] {
+                [left, right] => (left, right),
+                _ => return Err(line.error(from, "expected a pair of elements")),
+            };
+            let to = match to.chars().collect::<Vec<char>>()[..] {
+                [element] => element,
+                _ => return Err(line.error(to, "expected a single element")),
+            };
+
             Ok((from, to))
         })
-        .collect::<eyre::Result<_>>()?;
+        .collect::<Result<_, _>>()?;
 
     Ok(Input { template, rules })
 }
@@ -47,39 +58,53 @@ fn count_pairs(template: &[char]) -> HashMap<Pair, u64> {
     )
 }
 
-fn count_elements(polymer: &HashMap<Pair, u64>) -> HashMap<char, u64> {
-    polymer.iter().fold(
+fn count_elements(polymer: &HashMap<Pair, u64>) -> Option<HashMap<char, u64>> {
+    polymer.iter().try_fold(
         HashMap::with_capacity(polymer.len()),
         |mut acc, (&(left, _), &count)| {
-            *acc.entry(left).or_default() += count;
-            acc
+            let total = acc.entry(left).or_default();
+            *total = count.checked_add(*total)?;
+            Some(acc)
         },
     )
 }
 
+/// Yields the pair counts after each step, ending early if a count
+/// overflows.
 fn expand(
     initial: HashMap<Pair, u64>,
     rules: &HashMap<Pair, char>,
 ) -> impl Iterator<Item = HashMap<Pair, u64>> + '_ {
     iter::successors(Some(initial), |initial| {
-        let next = initial.iter().fold(
+        initial.iter().try_fold(
             HashMap::with_capacity(initial.len()),
-            |mut acc, (&pair @ (left, right), count)| {
+            |mut acc, (&pair @ (left, right), &count)| {
                 if let Some(&middle) = rules.get(&pair) {
-                    *acc.entry((left, middle)).or_default() += count;
-                    *acc.entry((middle, right)).or_default() += count;
+                    for pair in [(left, middle), (middle, right)] {
+                        let total = acc.entry(pair).or_default();
+                        *total = count.checked_add(*total)?;
+                    }
                 }
 
-                acc
+                Some(acc)
             },
-        );
-
-        Some(next)
+        )
     })
-    .skip(1)
 }
 
-fn solve(input: &Input, rounds: usize) -> eyre::Result<u64> {
+define_params! {
+    /// How long the polymer is expanded for.
+    pub struct Params {
+        /// Steps taken by part 1.
+        pub part1_steps: usize = 10,
+        /// Steps taken by part 2.
+        pub part2_steps: usize = 40,
+    }
+}
+
+/// Expands the polymer for `rounds` steps and subtracts the least common
+/// element count from the most common.
+pub fn solve(input: &Input, rounds: usize) -> eyre::Result<u64> {
     let last = input
         .template
         .last()
@@ -88,10 +113,9 @@ fn solve(input: &Input, rounds: usize) -> eyre::Result<u64> {
 
     let initial = count_pairs(&input.template);
     let polymer = expand(initial, &input.rules)
-        .take(rounds)
-        .last()
-        .context("unable to expand polymer")?;
-    let mut counts = count_elements(&polymer);
+        .nth(rounds)
+        .context("polymer counts overflowed")?;
+    let mut counts = count_elements(&polymer).context("polymer counts overflowed")?;
     *counts.entry(last).or_default() += 1;
 
     let ((_, min), (_, max)) = counts
@@ -103,12 +127,103 @@ fn solve(input: &Input, rounds: usize) -> eyre::Result<u64> {
     Ok(max - min)
 }
 
+/// Measures the polymer after 10 steps.
 #[aoc(day14, part1)]
-fn part1(input: &Input) -> eyre::Result<u64> {
-    solve(input, 10)
+pub fn part1(input: &Input) -> eyre::Result<u64> {
+    part1_with(input, &Params::default())
 }
 
+/// Measures the polymer after `part1_steps` steps.
+pub fn part1_with(input: &Input, params: &Params) -> eyre::Result<u64> {
+    solve(input, params.part1_steps)
+}
+
+/// Measures the polymer after 40 steps.
 #[aoc(day14, part2)]
-fn part2(input: &Input) -> eyre::Result<u64> {
-    solve(input, 40)
+pub fn part2(input: &Input) -> eyre::Result<u64> {
+    part2_with(input, &Params::default())
+}
+
+/// Measures the polymer after `part2_steps` steps.
+pub fn part2_with(input: &Input, params: &Params) -> eyre::Result<u64> {
+    solve(input, params.part2_steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.template, ['N', 'N', 'C', 'B']);
+        assert_eq!(input.rules.len(), 16);
+        assert_eq!(input.rules[&('C', 'H')], 'B');
+        assert_eq!(input.rules[&('C', 'N')], 'C');
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let initial = count_pairs(&input.template);
+        let mut steps = expand(initial, &input.rules).skip(1);
+
+        let expected = ["NCNBCHB", "NBCCNBBBCBHCB", "NBBBCNCCNBBNBNBBCHBHHBCHB"];
+        for polymer in expected {
+            let polymer = polymer.chars().collect::<Vec<char>>();
+            assert_eq!(steps.next(), Some(count_pairs(&polymer)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input)?, 1588);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input)?, 2188189693529);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_params() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let params = Params {
+            part1_steps: 0,
+            part2_steps: 5,
+        };
+        assert_eq!(part1_with(&input, &params)?, 1);
+        assert_eq!(part2_with(&input, &params)?, 33);
+
+        Ok(())
+    }
 }
@@ -1,27 +1,19 @@
-use std::{
-    cmp::Ordering,
-    collections::{BinaryHeap, HashMap},
+//! Day 15: Chiton
+
+use crate::{
+    error::{lines, ParseError},
+    grid::{Grid, Point},
+    params::define_params,
 };
+use std::{cmp::Ordering, collections::BinaryHeap};
 
-type Point = (i16, i16);
+/// The risk level of each position in the cave.
+pub type Input = Grid<u32>;
 
+/// Parses the risk levels, one digit per position.
 #[aoc_generator(day15)]
-fn generator(input: &str) -> Option<HashMap<Point, u32>> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(x, c)| c.to_digit(10).map(|n| ((x as i16, y as i16), n)))
-        })
-        .collect()
-}
-
-fn neighbors((x, y): Point) -> impl Iterator<Item = Point> {
-    [(0, -1), (-1, 0), (1, 0), (0, 1)]
-        .into_iter()
-        .map(move |(dx, dy)| (x + dx, y + dy))
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    Grid::parse_digits(lines(15, input))
 }
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -32,7 +24,7 @@ struct SearchEntry {
 
 impl PartialOrd for SearchEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.risk.partial_cmp(&other.risk).map(|o| o.reverse())
+        Some(self.cmp(other))
     }
 }
 
@@ -42,11 +34,13 @@ impl Ord for SearchEntry {
     }
 }
 
-fn search(grid: &HashMap<Point, u32>) -> u32 {
-    let goal = grid.keys().max().cloned().unwrap();
+/// Finds the lowest total risk of any path from the top left to the bottom
+/// right, or `None` for an empty cave.
+pub fn search(grid: &Grid<u32>) -> Option<u32> {
+    let goal = (grid.width().checked_sub(1)?, grid.height().checked_sub(1)?);
 
-    let mut total_risk = HashMap::new();
-    total_risk.insert((0, 0), 0);
+    let mut total_risk = grid.map(|_| u32::MAX);
+    total_risk[(0, 0)] = 0;
 
     let mut search = BinaryHeap::new();
     search.push(SearchEntry {
@@ -59,50 +53,162 @@ fn search(grid: &HashMap<Point, u32>) -> u32 {
             break;
         }
 
-        neighbors(entry.point)
-            .filter(|next| grid.contains_key(next))
-            .for_each(|next| {
-                if !total_risk.contains_key(&next)
-                    || total_risk[&entry.point] + grid[&next] < total_risk[&next]
-                {
-                    let risk = total_risk[&entry.point] + grid[&next];
-                    total_risk.insert(next, risk);
-
-                    search.push(SearchEntry { point: next, risk });
-                }
-            })
+        if entry.risk > total_risk[entry.point] {
+            continue;
+        }
+
+        for next in grid.neighbors4(entry.point) {
+            let risk = entry.risk + grid[next];
+            if risk < total_risk[next] {
+                total_risk[next] = risk;
+                search.push(SearchEntry { point: next, risk });
+            }
+        }
     }
 
-    total_risk[&goal]
+    Some(total_risk[goal])
 }
 
-fn expand(grid: &HashMap<Point, u32>) -> HashMap<Point, u32> {
-    let (endx, endy) = grid.keys().max().cloned().unwrap();
-    let width = endx + 1;
-    let height = endy + 1;
-
-    (0..5)
-        .flat_map(|tx| {
-            (0..5).flat_map(move |ty| {
-                grid.iter().map(move |(&(x, y), &risk)| {
-                    let x = x + (tx as i16 * width);
-                    let y = y + (ty as i16 * height);
-                    let risk = 1 + (risk - 1 + tx + ty) % 9;
-
-                    ((x, y), risk)
-                })
-            })
-        })
-        .collect()
+/// Tiles the cave `tiles` times in each direction, incrementing the risk
+/// levels of each tile after the first, or `None` if the cave would be too
+/// large.
+pub fn expand(grid: &Grid<u32>, tiles: usize) -> Option<Grid<u32>> {
+    let width = grid.width();
+    let height = grid.height();
+
+    let expanded = Grid::from_fn(
+        width.checked_mul(tiles)?,
+        height.checked_mul(tiles)?,
+        |(x, y)| {
+            let risk = grid[(x % width, y % height)];
+            if x < width && y < height {
+                return risk;
+            }
+
+            let tx = (x / width % 9) as u32;
+            let ty = (y / height % 9) as u32;
+            1 + (risk + 8 + tx + ty) % 9
+        },
+    );
+
+    Some(expanded)
 }
 
+define_params! {
+    /// How many times the cave is tiled in each direction.
+    pub struct Params {
+        /// Tiles searched by part 1.
+        pub part1_tiles: usize = 1,
+        /// Tiles searched by part 2.
+        pub part2_tiles: usize = 5,
+    }
+}
+
+/// Finds the lowest total risk through the cave.
 #[aoc(day15, part1)]
-fn part1(input: &HashMap<Point, u32>) -> u32 {
-    search(input)
+pub fn part1(input: &Input) -> Option<u32> {
+    part1_with(input, &Params::default())
+}
+
+/// Finds the lowest total risk through the cave tiled `part1_tiles` times.
+pub fn part1_with(input: &Input, params: &Params) -> Option<u32> {
+    search(&expand(input, params.part1_tiles)?)
 }
 
+/// Finds the lowest total risk through the expanded cave.
 #[aoc(day15, part2)]
-fn part2(input: &HashMap<Point, u32>) -> u32 {
-    let grid = expand(input);
-    search(&grid)
+pub fn part2(input: &Input) -> Option<u32> {
+    part2_with(input, &Params::default())
+}
+
+/// Finds the lowest total risk through the cave tiled `part2_tiles` times.
+pub fn part2_with(input: &Input, params: &Params) -> Option<u32> {
+    search(&expand(input, params.part2_tiles)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::ContextCompat;
+
+    const EXAMPLE: &str = "1163751742
+1381373672
+2136511328
+3694931569
+7463417111
+1319128137
+1359912421
+3125421639
+1293138521
+2311944581";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.width(), 10);
+        assert_eq!(input.height(), 10);
+        assert_eq!(input[(2, 0)], 6);
+        assert_eq!(input[(9, 9)], 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let expanded = expand(&input, 5).context("cave too large")?;
+        assert_eq!(expanded.width(), 50);
+        assert_eq!(expanded.height(), 50);
+        assert_eq!(
+            expanded.row(0).context("missing row")?,
+            "11637517422274862853338597396444961841755517295286"
+                .chars()
+                .map(|c| c.to_digit(10).unwrap_or_default())
+                .collect::<Vec<u32>>()
+        );
+        assert_eq!(expanded[(49, 49)], 9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), Some(40));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), Some(315));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_risk() -> eyre::Result<()> {
+        let input = parse("10\n01")?;
+        assert_eq!(part1(&input), Some(1));
+
+        let expanded = expand(&input, 2).context("cave too large")?;
+        assert_eq!(expanded[(1, 0)], 0);
+        assert_eq!(expanded[(3, 0)], 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_params() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let params = Params {
+            part1_tiles: 0,
+            part2_tiles: 2,
+        };
+        assert_eq!(part1_with(&input, &params), None);
+        assert_eq!(part2_with(&input, &params), Some(101));
+
+        Ok(())
+    }
 }
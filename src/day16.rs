@@ -1,32 +1,45 @@
-use eyre::eyre;
+//! Day 16: Packet Decoder
+
+use crate::error::{empty_input, lines, Line, ParseError};
+use eyre::bail;
+use itertools::Itertools;
 use nom::{
-    bits,
-    bits::complete::{tag, take},
-    branch::alt,
-    bytes::complete::take_while_m_n,
-    combinator::{map, map_res, verify},
-    multi::{length_count, many1, many_till},
-    sequence::preceded,
+    bits::complete::take,
+    bytes::complete::take as take_chars,
+    combinator::{map_res, verify},
+    multi::many1,
     Finish, IResult,
 };
+use num_bigint::BigUint;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    io::{self, BufReader, Read},
+    marker::PhantomData,
+    mem,
+    ops::{AddAssign, Shl, Shr},
+};
 
 type BitSlice<'a> = (&'a [u8], usize);
 
-#[derive(Debug, Clone)]
-enum Packet {
+/// A decoded BITS packet, with its literals held as `V`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet<V = u64> {
     Literal {
         version: u8,
-        value: u64,
+        value: V,
     },
     Operator {
         version: u8,
         operation: Operation,
-        packets: Vec<Packet>,
+        packets: Vec<Packet<V>>,
     },
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Operation {
+/// The operation an operator packet applies to its sub-packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Operation {
     Sum,
     Product,
     Minimum,
@@ -36,24 +49,211 @@ enum Operation {
     EqualTo,
 }
 
+impl Operation {
+    fn from_type(packet_type: u8) -> Option<Self> {
+        let operation = match packet_type {
+            0 => Operation::Sum,
+            1 => Operation::Product,
+            2 => Operation::Minimum,
+            3 => Operation::Maximum,
+            5 => Operation::GreaterThan,
+            6 => Operation::LessThan,
+            7 => Operation::EqualTo,
+            _ => return None,
+        };
+
+        Some(operation)
+    }
+
+    fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Operation::GreaterThan | Operation::LessThan | Operation::EqualTo
+        )
+    }
+
+    fn packet_type(self) -> u8 {
+        match self {
+            Operation::Sum => 0,
+            Operation::Product => 1,
+            Operation::Minimum => 2,
+            Operation::Maximum => 3,
+            Operation::GreaterThan => 5,
+            Operation::LessThan => 6,
+            Operation::EqualTo => 7,
+        }
+    }
+}
+
+/// A number that literals are decoded into and packets are evaluated with.
+/// A `u64` can overflow, while a [`BigUint`] holds literals and results of
+/// any size.
+pub trait Value: Clone + Ord + fmt::Display + From<u8> {
+    /// Appends a 4-bit literal group, or returns `None` if the value no
+    /// longer fits.
+    fn push_group(self, group: u8) -> Option<Self>;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+}
+
+impl Value for u64 {
+    fn push_group(self, group: u8) -> Option<Self> {
+        (self >> 60 == 0).then(|| self << 4 | u64::from(group))
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *other)
+    }
+}
+
+impl Value for BigUint {
+    fn push_group(self, group: u8) -> Option<Self> {
+        Some(self << 4u8 | BigUint::from(group))
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+/// Reads a byte as two hex digits. Taking exactly two characters, rather than
+/// a run of hex digits, keeps reading long transmissions linear.
 fn hex_digit(input: &str) -> IResult<&str, u8> {
-    map_res(take_while_m_n(2, 2, |c: char| c.is_digit(16)), |s: &str| {
-        u8::from_str_radix(s, 16)
-    })(input)
+    map_res(
+        verify(take_chars(2usize), |s: &str| {
+            s.chars().all(|c| c.is_ascii_hexdigit())
+        }),
+        |s: &str| u8::from_str_radix(s, 16),
+    )(input)
 }
 
 fn hex_string(input: &str) -> IResult<&str, Vec<u8>> {
     many1(hex_digit)(input)
 }
 
+/// Why a transmission could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The transmission ended partway through the named field.
+    Truncated(&'static str),
+    /// A literal is too large for the type it is decoded into.
+    LiteralOverflow,
+    /// An operator's sub-packets ran past the length it gave for them.
+    LengthOverrun { length: usize, consumed: usize },
+    /// A comparison has other than two sub-packets.
+    Comparison {
+        operation: Operation,
+        packets: usize,
+    },
+    /// The bits after the outermost packet are not all zero.
+    Padding,
+    /// A packet is nested inside more operators than the decoder allows.
+    TooDeep(usize),
+}
+
+impl fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeErrorKind::Truncated(field) => write!(f, "truncated {}", field),
+            DecodeErrorKind::LiteralOverflow => write!(f, "literal is too large"),
+            DecodeErrorKind::LengthOverrun { length, consumed } => write!(
+                f,
+                "sub-packets take {} bits, overrunning their length of {}",
+                consumed, length
+            ),
+            DecodeErrorKind::Comparison { operation, packets } => write!(
+                f,
+                "{} needs exactly 2 sub-packets, found {}",
+                operation, packets
+            ),
+            DecodeErrorKind::Padding => write!(f, "non-zero padding"),
+            DecodeErrorKind::TooDeep(max_depth) => {
+                write!(f, "nested inside more than {} operators", max_depth)
+            }
+        }
+    }
+}
+
+/// A malformed transmission, pointing at the bit the problem starts at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    /// The offset of the offending bit from the start of the transmission.
+    pub offset: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bit {}: {}", self.offset, self.kind)
+    }
+}
+
+impl Error for DecodeError {}
+
+/// The limits put on a transmission while decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// How many operators a packet can be nested inside. Packets are decoded,
+    /// encoded and written out recursively, so this bounds the stack they
+    /// use.
+    pub max_depth: usize,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self { max_depth: 256 }
+    }
+}
+
+/// A decode error as the bit parsers report it, counting the bits left
+/// from where it starts since the parsers don't know the offset.
+#[derive(Debug)]
+struct BitsError {
+    remaining: usize,
+    kind: DecodeErrorKind,
+}
+
+type BitsResult<'a, O> = IResult<BitSlice<'a>, O, BitsError>;
+
+fn remaining((bytes, offset): BitSlice) -> usize {
+    bytes.len() * 8 - offset
+}
+
+fn fail<O>(input: BitSlice, kind: DecodeErrorKind) -> BitsResult<O> {
+    Err(nom::Err::Error(BitsError {
+        remaining: remaining(input),
+        kind,
+    }))
+}
+
+/// Takes the next `count` bits, naming the field they hold if the
+/// transmission ends first.
+fn field<'a, O>(count: usize, name: &'static str) -> impl Fn(BitSlice<'a>) -> BitsResult<'a, O>
+where
+    O: From<u8> + AddAssign + Shl<usize, Output = O> + Shr<usize, Output = O>,
+{
+    move |input| match take::<_, _, _, ()>(count)(input) {
+        Ok(result) => Ok(result),
+        Err(_) => fail(input, DecodeErrorKind::Truncated(name)),
+    }
+}
+
 struct PacketHeader {
     version: u8,
     packet_type: u8,
 }
 
-fn header(input: BitSlice) -> IResult<BitSlice, PacketHeader> {
-    let (input, version) = take(3usize)(input)?;
-    let (input, packet_type) = take(3usize)(input)?;
+fn header(input: BitSlice) -> BitsResult<PacketHeader> {
+    let (input, version) = field(3, "packet version")(input)?;
+    let (input, packet_type) = field(3, "packet type")(input)?;
 
     Ok((
         input,
@@ -64,87 +264,101 @@ fn header(input: BitSlice) -> IResult<BitSlice, PacketHeader> {
     ))
 }
 
-fn variable_length_value(input: BitSlice) -> IResult<BitSlice, u64> {
-    let nibble = |input| take::<_, u8, _, _>(4usize)(input);
-    let (input, nibbles) = map(
-        many_till(
-            preceded(tag(1, 1usize), nibble),
-            preceded(tag(0, 1usize), nibble),
-        ),
-        |(mut parts, part)| {
-            parts.push(part);
-            parts
-        },
-    )(input)?;
-
-    let value = nibbles
-        .iter()
-        .rev()
-        .enumerate()
-        .fold(0, |acc, (offset, &nibble)| {
-            acc | (nibble as u64) << (offset * 4)
-        });
-
-    Ok((input, value))
-}
-
-fn literal_packet(input: BitSlice) -> IResult<BitSlice, Packet> {
-    let (input, header) = verify(header, |header| header.packet_type == 4)(input)?;
-    let (input, value) = variable_length_value(input)?;
+/// Reads the groups of a literal, returning its value and the number of
+/// groups it was written with.
+fn variable_length_value<V: Value>(input: BitSlice) -> BitsResult<(V, usize)> {
+    let start = input;
+    let mut input = input;
+    let mut value = V::from(0);
+    let mut groups = 0;
+    loop {
+        let (next, group): (_, u8) = field(5, "literal group")(input)?;
+        value = match value.push_group(group & 0xf) {
+            Some(value) => value,
+            None => return fail(start, DecodeErrorKind::LiteralOverflow),
+        };
 
-    let version = header.version;
-    Ok((input, Packet::Literal { version, value }))
+        groups += 1;
+        input = next;
+        if group & 0x10 == 0 {
+            return Ok((input, (value, groups)));
+        }
+    }
 }
 
-fn consumed_length(a: BitSlice, b: BitSlice) -> usize {
-    let (input_a, index_a) = a;
-    let (input_b, index_b) = b;
-    (input_a.len() * 8 - index_a) - (input_b.len() * 8 - index_b)
+/// Reads an operator's length type and length.
+fn operator_length(input: BitSlice) -> BitsResult<(LengthType, usize)> {
+    let (input, length_type): (_, u8) = field(1, "length type")(input)?;
+    if length_type == 0 {
+        let (input, length) = field(15, "sub-packet length")(input)?;
+        Ok((input, (LengthType::Bits, length)))
+    } else {
+        let (input, count) = field(11, "sub-packet count")(input)?;
+        Ok((input, (LengthType::Count, count)))
+    }
 }
 
-fn operator_subpackets(input: BitSlice) -> IResult<BitSlice, Vec<Packet>> {
-    let subpackets_by_length = preceded(tag(0, 1usize), |input| {
-        let (input, length) = take(15usize)(input)?;
-
-        let mut packets = vec![];
-        let mut remaining_input = input;
-        let mut consumed = 0;
-        while consumed < length {
-            let (next_input, packet) = packet(remaining_input)?;
+fn operator_subpackets<'a, V: Value>(
+    input: BitSlice<'a>,
+    depth: usize,
+    options: &DecodeOptions,
+) -> BitsResult<'a, Vec<Packet<V>>> {
+    let (mut input, (length_type, length)) = operator_length(input)?;
 
-            consumed += consumed_length(remaining_input, next_input);
-            remaining_input = next_input;
+    let mut packets = vec![];
+    match length_type {
+        LengthType::Bits => {
+            let mut consumed = 0;
+            while consumed < length {
+                let (next, packet) = packet(input, depth + 1, options)?;
+                consumed += remaining(input) - remaining(next);
+                if consumed > length {
+                    return fail(input, DecodeErrorKind::LengthOverrun { length, consumed });
+                }
 
-            packets.push(packet);
+                input = next;
+                packets.push(packet);
+            }
         }
+        LengthType::Count => {
+            for _ in 0..length {
+                let (next, packet) = packet(input, depth + 1, options)?;
+                input = next;
+                packets.push(packet);
+            }
+        }
+    }
 
-        Ok((remaining_input, packets))
-    });
-
-    let subpackets_by_count = preceded(
-        tag(1, 1usize),
-        length_count(take::<_, u16, _, _>(11usize), packet),
-    );
-
-    alt((subpackets_by_length, subpackets_by_count))(input)
+    Ok((input, packets))
 }
 
-fn operator_packet(input: BitSlice) -> IResult<BitSlice, Packet> {
-    let (input, header) = verify(header, |header| header.packet_type != 4)(input)?;
-    let (input, packets) = operator_subpackets(input)?;
+fn packet<'a, V: Value>(
+    input: BitSlice<'a>,
+    depth: usize,
+    options: &DecodeOptions,
+) -> BitsResult<'a, Packet<V>> {
+    let start = input;
+    if depth > options.max_depth {
+        return fail(start, DecodeErrorKind::TooDeep(options.max_depth));
+    }
 
+    let (input, header) = header(input)?;
     let version = header.version;
-    let operation = match header.packet_type {
-        0 => Operation::Sum,
-        1 => Operation::Product,
-        2 => Operation::Minimum,
-        3 => Operation::Maximum,
-        5 => Operation::GreaterThan,
-        6 => Operation::LessThan,
-        7 => Operation::EqualTo,
-        _ => unreachable!(),
+
+    let operation = match Operation::from_type(header.packet_type) {
+        Some(operation) => operation,
+        None => {
+            let (input, (value, _)) = variable_length_value(input)?;
+            return Ok((input, Packet::Literal { version, value }));
+        }
     };
 
+    let (input, packets) = operator_subpackets(input, depth, options)?;
+    if operation.is_comparison() && packets.len() != 2 {
+        let packets = packets.len();
+        return fail(start, DecodeErrorKind::Comparison { operation, packets });
+    }
+
     Ok((
         input,
         Packet::Operator {
@@ -155,89 +369,1835 @@ fn operator_packet(input: BitSlice) -> IResult<BitSlice, Packet> {
     ))
 }
 
-fn packet(input: BitSlice) -> IResult<BitSlice, Packet> {
-    alt((literal_packet, operator_packet))(input)
+/// Finds the first set bit, counting from the start of `input`.
+fn first_set_bit((bytes, offset): BitSlice) -> Option<usize> {
+    (offset..bytes.len() * 8)
+        .find(|bit| bytes[bit / 8] & (0x80 >> (bit % 8)) != 0)
+        .map(|bit| bit - offset)
 }
 
-fn parse_packet(input: &[u8]) -> IResult<&[u8], Packet> {
-    bits(packet)(input)
+/// Decodes a binary transmission into its outermost packet. Everything after
+/// the packet must be zero padding.
+pub fn decode<V: Value>(data: &[u8]) -> Result<Packet<V>, DecodeError> {
+    decode_with(data, &DecodeOptions::default())
 }
 
-#[aoc_generator(day16)]
-fn generator(input: &str) -> eyre::Result<Packet> {
-    let (_, data) = hex_string(input)
+/// Decodes a binary transmission like [`decode`], within the given limits.
+pub fn decode_with<V: Value>(
+    data: &[u8],
+    options: &DecodeOptions,
+) -> Result<Packet<V>, DecodeError> {
+    let total = data.len() * 8;
+    let (rest, packet) = packet((data, 0), 0, options)
         .finish()
-        .map_err(|_| eyre!("unable to parse hex"))?;
+        .map_err(|error| DecodeError {
+            offset: total - error.remaining,
+            kind: error.kind,
+        })?;
 
-    let (_, packet) = parse_packet(&data)
-        .finish()
-        .map_err(|_| eyre!("unable to parse packets"))?;
+    if let Some(bit) = first_set_bit(rest) {
+        return Err(DecodeError {
+            offset: total - remaining(rest) + bit,
+            kind: DecodeErrorKind::Padding,
+        });
+    }
 
     Ok(packet)
 }
 
-#[aoc(day16, part1)]
-fn part1(input: &Packet) -> usize {
-    let mut search = vec![input];
-    let mut count = 0;
+/// Decodes the hex digits of a transmission into bytes.
+fn transmission<'a>(line: &Line<'a>) -> Result<(&'a str, Vec<u8>), ParseError> {
+    let text = line.check_chars(
+        line.text(),
+        |c| c.is_ascii_hexdigit(),
+        "expected a hex digit",
+    )?;
 
-    while let Some(packet) = search.pop() {
-        match packet {
-            Packet::Literal { version, .. } => count += *version as usize,
-            Packet::Operator {
-                version, packets, ..
-            } => {
-                count += *version as usize;
-                search.extend(packets);
+    let (rest, data) = hex_string(text)
+        .finish()
+        .map_err(|e| line.error(e.input, "expected hex digits"))?;
+    if !rest.is_empty() {
+        return Err(line.error(rest, "expected hex digits"));
+    }
+
+    Ok((text, data))
+}
+
+/// Points a decode error at the hex digit holding the offending bit.
+fn decode_error(line: &Line, text: &str, error: DecodeError) -> ParseError {
+    let digit = (error.offset / 4).min(text.len());
+    line.error(&text[digit..], error.to_string())
+}
+
+fn parse_with<V: Value>(input: &str) -> Result<Packet<V>, ParseError> {
+    let line = lines(16, input).next().ok_or_else(|| empty_input(16))?;
+    let (text, data) = transmission(&line)?;
+
+    decode(&data).map_err(|error| decode_error(&line, text, error))
+}
+
+/// Decodes the hexadecimal transmission into its outermost packet.
+#[aoc_generator(day16)]
+pub fn parse(input: &str) -> Result<Packet, ParseError> {
+    parse_with(input)
+}
+
+/// Decodes the transmission like [`parse`], but with literals of any size.
+pub fn parse_big(input: &str) -> Result<Packet<BigUint>, ParseError> {
+    parse_with(input)
+}
+
+/// A failure while decoding a stream of packets.
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    /// A byte other than a hex digit or whitespace, at the given offset in
+    /// the stream.
+    InvalidHex {
+        position: usize,
+        byte: u8,
+    },
+    Decode(DecodeError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "unable to read transmission: {}", error),
+            StreamError::InvalidHex { position, byte } => write!(
+                f,
+                "byte {}: expected a hex digit, found {:?}",
+                position, *byte as char
+            ),
+            StreamError::Decode(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::Io(error) => Some(error),
+            StreamError::Decode(error) => Some(error),
+            StreamError::InvalidHex { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> Self {
+        StreamError::Io(error)
+    }
+}
+
+/// The bits of a hex transmission, read a digit at a time.
+struct BitStream<R> {
+    bytes: io::Bytes<BufReader<R>>,
+    /// The number of bytes read from the stream.
+    position: usize,
+    nibble: u8,
+    /// The bits of `nibble` not read yet.
+    left: u8,
+    /// The number of bits read so far.
+    offset: usize,
+    /// Bits that were looked ahead at and put back, as a run of zeros
+    /// followed by a one.
+    zeros: usize,
+    one: bool,
+}
+
+impl<R: Read> BitStream<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            position: 0,
+            nibble: 0,
+            left: 0,
+            offset: 0,
+            zeros: 0,
+            one: false,
+        }
+    }
+
+    fn next_digit(&mut self) -> Result<Option<u8>, StreamError> {
+        for byte in &mut self.bytes {
+            let byte = byte?;
+            let position = self.position;
+            self.position += 1;
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+
+            return match (byte as char).to_digit(16) {
+                Some(digit) => Ok(Some(digit as u8)),
+                None => Err(StreamError::InvalidHex { position, byte }),
+            };
+        }
+
+        Ok(None)
+    }
+
+    fn read_bit(&mut self) -> Result<Option<bool>, StreamError> {
+        let bit = if self.zeros > 0 {
+            self.zeros -= 1;
+            false
+        } else if self.one {
+            self.one = false;
+            true
+        } else {
+            if self.left == 0 {
+                match self.next_digit()? {
+                    Some(nibble) => self.nibble = nibble,
+                    None => return Ok(None),
+                }
+                self.left = 4;
+            }
+
+            self.left -= 1;
+            self.nibble >> self.left & 1 == 1
+        };
+
+        self.offset += 1;
+        Ok(Some(bit))
+    }
+
+    /// Reads the next `count` bits, naming the field they hold if the
+    /// transmission ends first.
+    fn field(&mut self, count: usize, name: &'static str) -> Result<usize, StreamError> {
+        let start = self.offset;
+        let mut value = 0;
+        for _ in 0..count {
+            match self.read_bit()? {
+                Some(bit) => value = value << 1 | bit as usize,
+                None => {
+                    return Err(StreamError::Decode(DecodeError {
+                        offset: start,
+                        kind: DecodeErrorKind::Truncated(name),
+                    }))
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Looks ahead for another packet, returning `false` if only zero padding
+    /// is left. The bits looked at are put back.
+    fn has_packet(&mut self) -> Result<bool, StreamError> {
+        let mut zeros = 0;
+        loop {
+            match self.read_bit()? {
+                None => return Ok(false),
+                Some(false) => zeros += 1,
+                Some(true) => {
+                    self.offset -= zeros + 1;
+                    self.zeros = zeros;
+                    self.one = true;
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    fn fail<O>(&self, offset: usize, kind: DecodeErrorKind) -> Result<O, StreamError> {
+        Err(StreamError::Decode(DecodeError { offset, kind }))
+    }
+}
+
+fn read_packet<R: Read, V: Value>(
+    bits: &mut BitStream<R>,
+    depth: usize,
+    options: &DecodeOptions,
+) -> Result<Packet<V>, StreamError> {
+    let start = bits.offset;
+    if depth > options.max_depth {
+        return bits.fail(start, DecodeErrorKind::TooDeep(options.max_depth));
+    }
+
+    let version = bits.field(3, "packet version")? as u8;
+    let packet_type = bits.field(3, "packet type")? as u8;
+
+    let operation = match Operation::from_type(packet_type) {
+        Some(operation) => operation,
+        None => {
+            let literal_start = bits.offset;
+            let mut value = V::from(0);
+            loop {
+                let group = bits.field(5, "literal group")? as u8;
+                value = match value.push_group(group & 0xf) {
+                    Some(value) => value,
+                    None => return bits.fail(literal_start, DecodeErrorKind::LiteralOverflow),
+                };
+
+                if group & 0x10 == 0 {
+                    return Ok(Packet::Literal { version, value });
+                }
+            }
+        }
+    };
+
+    let mut packets = vec![];
+    if bits.field(1, "length type")? == 0 {
+        let length = bits.field(15, "sub-packet length")?;
+        let end = bits.offset + length;
+        while bits.offset < end {
+            let packet_start = bits.offset;
+            packets.push(read_packet(bits, depth + 1, options)?);
+            if bits.offset > end {
+                let consumed = length + bits.offset - end;
+                return bits.fail(
+                    packet_start,
+                    DecodeErrorKind::LengthOverrun { length, consumed },
+                );
+            }
+        }
+    } else {
+        let count = bits.field(11, "sub-packet count")?;
+        for _ in 0..count {
+            packets.push(read_packet(bits, depth + 1, options)?);
+        }
+    }
+
+    if operation.is_comparison() && packets.len() != 2 {
+        let packets = packets.len();
+        return bits.fail(start, DecodeErrorKind::Comparison { operation, packets });
+    }
+
+    Ok(Packet::Operator {
+        version,
+        operation,
+        packets,
+    })
+}
+
+/// Decodes packets one after another from a hex transmission, reading it
+/// as they're needed rather than all at once.
+///
+/// Packets follow each other directly, with no padding between them, and
+/// whitespace between hex digits is ignored. Once only zero bits are left
+/// they're taken to be the padding and the stream ends. Decoding stops at
+/// the first error.
+pub struct PacketStream<R, V = u64> {
+    bits: BitStream<R>,
+    options: DecodeOptions,
+    done: bool,
+    value: PhantomData<V>,
+}
+
+impl<R: Read, V: Value> PacketStream<R, V> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, DecodeOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: DecodeOptions) -> Self {
+        Self {
+            bits: BitStream::new(reader),
+            options,
+            done: false,
+            value: PhantomData,
+        }
+    }
+
+    /// The number of bits decoded so far.
+    pub fn offset(&self) -> usize {
+        self.bits.offset
+    }
+}
+
+impl<R: Read, V: Value> Iterator for PacketStream<R, V> {
+    type Item = Result<Packet<V>, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let packet = match self.bits.has_packet() {
+            Ok(true) => read_packet(&mut self.bits, 0, &self.options),
+            Ok(false) => {
+                self.done = true;
+                return None;
             }
+            Err(error) => Err(error),
+        };
+
+        self.done = packet.is_err();
+        Some(packet)
+    }
+}
+
+/// How an operator packet records the extent of its sub-packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthType {
+    /// Length type 0, the total length of the sub-packets in 15 bits.
+    Bits,
+    /// Length type 1, the number of sub-packets in 11 bits.
+    Count,
+}
+
+/// The choices left open by the BITS format when encoding a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// The form every operator packet is written in.
+    pub length_type: LengthType,
+    /// The least number of 4-bit groups a literal is written with, padding
+    /// smaller values with leading zero groups.
+    pub literal_groups: usize,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            length_type: LengthType::Bits,
+            literal_groups: 1,
         }
     }
+}
+
+fn write_bits(bits: &mut Vec<bool>, value: u64, count: usize) {
+    bits.extend((0..count).rev().map(|offset| (value >> offset) & 1 == 1));
+}
+
+fn write_header(bits: &mut Vec<bool>, version: u8, packet_type: u8) -> eyre::Result<()> {
+    if version >= 8 {
+        bail!("version {} does not fit in 3 bits", version);
+    }
+
+    write_bits(bits, version.into(), 3);
+    write_bits(bits, packet_type.into(), 3);
+    Ok(())
+}
 
-    count
+/// The `group`th 4-bit group of `value` counting from the least significant,
+/// which is zero past the end of the value.
+fn literal_nibble(value: u64, group: usize) -> u64 {
+    group
+        .checked_mul(4)
+        .and_then(|shift| u32::try_from(shift).ok())
+        .and_then(|shift| value.checked_shr(shift))
+        .map_or(0, |nibble| nibble & 0xF)
 }
 
-fn evaluate(packet: &Packet) -> u64 {
+fn write_packet(
+    bits: &mut Vec<bool>,
+    packet: &Packet,
+    options: &EncodeOptions,
+) -> eyre::Result<()> {
     match packet {
-        Packet::Literal { value, .. } => *value,
+        &Packet::Literal { version, value } => {
+            write_header(bits, version, 4)?;
+
+            let significant = (64 - value.leading_zeros() as usize).div_ceil(4);
+            let groups = significant.max(options.literal_groups).max(1);
+            for group in (0..groups).rev() {
+                bits.push(group > 0);
+                write_bits(bits, literal_nibble(value, group), 4);
+            }
+        }
         Packet::Operator {
-            operation, packets, ..
-        } => match operation {
-            Operation::Sum => packets.iter().map(evaluate).sum(),
-            Operation::Product => packets.iter().map(evaluate).product(),
-            Operation::Minimum => packets.iter().map(evaluate).min().unwrap_or_default(),
-            Operation::Maximum => packets.iter().map(evaluate).max().unwrap_or_default(),
-            Operation::GreaterThan => {
-                let left = evaluate(&packets[0]);
-                let right = evaluate(&packets[1]);
-                if left > right {
-                    1
-                } else {
-                    0
-                }
+            version,
+            operation,
+            packets,
+        } => {
+            write_header(bits, *version, operation.packet_type())?;
+            if operation.is_comparison() && packets.len() != 2 {
+                bail!(
+                    "{} needs exactly 2 sub-packets, found {}",
+                    operation,
+                    packets.len()
+                );
             }
-            Operation::LessThan => {
-                let left = evaluate(&packets[0]);
-                let right = evaluate(&packets[1]);
-                if left < right {
-                    1
-                } else {
-                    0
+
+            match options.length_type {
+                LengthType::Bits => {
+                    let mut subpackets = vec![];
+                    for packet in packets {
+                        write_packet(&mut subpackets, packet, options)?;
+                    }
+                    if subpackets.len() >= 1 << 15 {
+                        bail!(
+                            "{} bits of sub-packets do not fit in a 15-bit length",
+                            subpackets.len()
+                        );
+                    }
+
+                    bits.push(false);
+                    write_bits(bits, subpackets.len() as u64, 15);
+                    bits.extend(subpackets);
                 }
-            }
-            Operation::EqualTo => {
-                let left = evaluate(&packets[0]);
-                let right = evaluate(&packets[1]);
-                if left == right {
-                    1
-                } else {
-                    0
+                LengthType::Count => {
+                    if packets.len() >= 1 << 11 {
+                        bail!(
+                            "{} sub-packets do not fit in an 11-bit count",
+                            packets.len()
+                        );
+                    }
+
+                    bits.push(true);
+                    write_bits(bits, packets.len() as u64, 11);
+                    for packet in packets {
+                        write_packet(bits, packet, options)?;
+                    }
                 }
             }
-        },
+        }
     }
+
+    Ok(())
 }
 
-#[aoc(day16, part2)]
-fn part2(input: &Packet) -> u64 {
-    evaluate(input)
+impl Packet {
+    /// Encodes the packet as bits, without any trailing padding.
+    pub fn to_bits(&self, options: &EncodeOptions) -> eyre::Result<Vec<bool>> {
+        let mut bits = vec![];
+        write_packet(&mut bits, self, options)?;
+        Ok(bits)
+    }
+
+    /// Encodes the packet as bytes, padding the last byte with zeros.
+    pub fn to_bytes(&self, options: &EncodeOptions) -> eyre::Result<Vec<u8>> {
+        let bytes = self
+            .to_bits(options)?
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |acc, (offset, &bit)| acc | (bit as u8) << (7 - offset))
+            })
+            .collect();
+
+        Ok(bytes)
+    }
+
+    /// Encodes the packet as a hexadecimal transmission, the inverse of
+    /// [`parse`].
+    pub fn to_hex(&self, options: &EncodeOptions) -> eyre::Result<String> {
+        let hex = self
+            .to_bytes(options)?
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+
+        Ok(hex)
+    }
+}
+
+/// The header and layout of a single packet in a transmission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    /// The offset of the packet's first bit from the start of the
+    /// transmission.
+    pub offset: usize,
+    /// How many operators the packet is nested inside.
+    pub depth: usize,
+    pub version: u8,
+    pub packet_type: u8,
+    pub body: Body,
+}
+
+/// What follows a packet's header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Body {
+    Literal {
+        value: u64,
+        groups: usize,
+    },
+    Operator {
+        operation: Operation,
+        length_type: LengthType,
+        /// The number of bits or of sub-packets, depending on the length
+        /// type.
+        length: u16,
+    },
+}
+
+/// A transmission listed one packet per line, in the order they appear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    pub instructions: Vec<Instruction>,
+}
+
+fn disassemble_packet<'a>(
+    input: BitSlice<'a>,
+    total: usize,
+    depth: usize,
+    options: &DecodeOptions,
+    instructions: &mut Vec<Instruction>,
+) -> BitsResult<'a, ()> {
+    let offset = total - remaining(input);
+    if depth > options.max_depth {
+        return fail(input, DecodeErrorKind::TooDeep(options.max_depth));
+    }
+
+    let (input, header) = header(input)?;
+    let mut push = |body| {
+        instructions.push(Instruction {
+            offset,
+            depth,
+            version: header.version,
+            packet_type: header.packet_type,
+            body,
+        })
+    };
+
+    let operation = match Operation::from_type(header.packet_type) {
+        Some(operation) => operation,
+        None => {
+            let (input, (value, groups)) = variable_length_value::<u64>(input)?;
+            push(Body::Literal { value, groups });
+            return Ok((input, ()));
+        }
+    };
+
+    let (mut input, (length_type, length)) = operator_length(input)?;
+    push(Body::Operator {
+        operation,
+        length_type,
+        length: length as u16,
+    });
+
+    match length_type {
+        LengthType::Bits => {
+            let end = remaining(input).saturating_sub(length);
+            while remaining(input) > end {
+                (input, _) = disassemble_packet(input, total, depth + 1, options, instructions)?;
+            }
+        }
+        LengthType::Count => {
+            for _ in 0..length {
+                (input, _) = disassemble_packet(input, total, depth + 1, options, instructions)?;
+            }
+        }
+    }
+
+    Ok((input, ()))
+}
+
+impl Disassembly {
+    /// Lists the packets of a hexadecimal transmission. Unlike [`parse`],
+    /// this doesn't check that comparisons have two sub-packets, that
+    /// sub-packets fit their operator's length or the padding, but it does
+    /// keep to the default [`DecodeOptions`].
+    pub fn new(input: &str) -> Result<Self, ParseError> {
+        let line = lines(16, input).next().ok_or_else(|| empty_input(16))?;
+        let (text, data) = transmission(&line)?;
+
+        let total = data.len() * 8;
+        let mut instructions = vec![];
+        let options = DecodeOptions::default();
+        disassemble_packet((&data, 0), total, 0, &options, &mut instructions)
+            .finish()
+            .map_err(|error| {
+                let error = DecodeError {
+                    offset: total - error.remaining,
+                    kind: error.kind,
+                };
+                decode_error(&line, text, error)
+            })?;
+
+        Ok(Self { instructions })
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::Sum => "sum",
+            Operation::Product => "product",
+            Operation::Minimum => "min",
+            Operation::Maximum => "max",
+            Operation::GreaterThan => "gt",
+            Operation::LessThan => "lt",
+            Operation::EqualTo => "eq",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>6}  {:indent$}v{} type {} ",
+            self.offset,
+            "",
+            self.version,
+            self.packet_type,
+            indent = self.depth * 2
+        )?;
+
+        match self.body {
+            Body::Literal { value, groups } => {
+                let plural = if groups == 1 { "" } else { "s" };
+                write!(f, "literal {} ({} group{})", value, groups, plural)
+            }
+            Body::Operator {
+                operation,
+                length_type: LengthType::Bits,
+                length,
+            } => write!(f, "{} length type 0, {} bits", operation, length),
+            Body::Operator {
+                operation,
+                length_type: LengthType::Count,
+                length,
+            } => {
+                let plural = if length == 1 { "" } else { "s" };
+                write!(
+                    f,
+                    "{} length type 1, {} packet{}",
+                    operation, length, plural
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<V> Packet<V> {
+    /// How tightly the packet binds when written as an infix expression.
+    fn precedence(&self) -> u8 {
+        match self {
+            Packet::Operator {
+                operation, packets, ..
+            } => match operation {
+                Operation::GreaterThan | Operation::LessThan | Operation::EqualTo
+                    if packets.len() == 2 =>
+                {
+                    1
+                }
+                Operation::Sum if packets.len() >= 2 => 2,
+                Operation::Product if packets.len() >= 2 => 3,
+                _ => 4,
+            },
+            Packet::Literal { .. } => 4,
+        }
+    }
+}
+
+/// Writes the packet as an infix expression, such as `(1 + 2) * (3 > 4)`.
+/// Minimum and maximum, and comparisons without exactly two sub-packets, are
+/// written as calls.
+impl<V: fmt::Display> fmt::Display for Packet<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (operation, packets) = match self {
+            Packet::Literal { value, .. } => return write!(f, "{}", value),
+            Packet::Operator {
+                operation, packets, ..
+            } => (*operation, packets),
+        };
+
+        let symbol = match operation {
+            Operation::Sum if packets.len() >= 2 => " + ",
+            Operation::Product if packets.len() >= 2 => " * ",
+            Operation::GreaterThan if packets.len() == 2 => " > ",
+            Operation::LessThan if packets.len() == 2 => " < ",
+            Operation::EqualTo if packets.len() == 2 => " == ",
+            _ => {
+                write!(f, "{}(", operation)?;
+                for (index, packet) in packets.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", packet)?;
+                }
+                return write!(f, ")");
+            }
+        };
+
+        // Comparisons don't chain, so a comparison inside another needs
+        // parentheses too.
+        let precedence = self.precedence();
+        for (index, packet) in packets.iter().enumerate() {
+            if index > 0 {
+                write!(f, "{}", symbol)?;
+            }
+            if packet.precedence() < precedence || (precedence == 1 && packet.precedence() == 1) {
+                write!(f, "({})", packet)?;
+            } else {
+                write!(f, "{}", packet)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Where a packet sits in the tree while it is being visited or folded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Context<'a> {
+    /// The index of each sub-packet on the way down from the outermost
+    /// packet, so the outermost packet has an empty path.
+    pub path: &'a [usize],
+    /// The operation of the enclosing operator, or `None` for the outermost
+    /// packet.
+    pub parent: Option<Operation>,
+}
+
+impl Context<'_> {
+    /// How many operators enclose the packet.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+}
+
+/// Hooks called on every packet of a tree, depth first.
+pub trait Visitor<V = u64> {
+    /// Called before the packet's sub-packets are visited.
+    fn pre(&mut self, _packet: &Packet<V>, _context: Context) {}
+
+    /// Called after the packet's sub-packets have been visited.
+    fn post(&mut self, _packet: &Packet<V>, _context: Context) {}
+}
+
+/// Builds a value bottom up from a tree, with each operator seeing the
+/// values built from its sub-packets.
+pub trait Fold<V = u64> {
+    type Output;
+
+    fn literal(&mut self, version: u8, value: &V, context: Context) -> Self::Output;
+
+    fn operator(
+        &mut self,
+        version: u8,
+        operation: Operation,
+        values: Vec<Self::Output>,
+        context: Context,
+    ) -> Self::Output;
+}
+
+/// An operator whose sub-packets are being folded.
+struct FoldFrame<'a, V, O> {
+    version: u8,
+    operation: Operation,
+    packets: &'a [Packet<V>],
+    parent: Option<Operation>,
+    /// The values folded from the sub-packets so far.
+    values: Vec<O>,
+}
+
+impl<V> Packet<V> {
+    /// Walks every packet depth first, calling the visitor's hooks. The walk
+    /// keeps its own stack, so it works on trees of any depth.
+    pub fn visit<T: Visitor<V> + ?Sized>(&self, visitor: &mut T) {
+        let mut path = vec![];
+        // Each packet on the way down, with its parent's operation and the
+        // index of the next sub-packet to visit.
+        let mut stack = vec![(self, None, 0)];
+        visitor.pre(
+            self,
+            Context {
+                path: &path,
+                parent: None,
+            },
+        );
+
+        while let Some((packet, parent, next)) = stack.last_mut() {
+            let packet = *packet;
+            let child = match packet {
+                Packet::Operator {
+                    operation, packets, ..
+                } => packets.get(*next).map(|child| (child, Some(*operation))),
+                Packet::Literal { .. } => None,
+            };
+
+            match child {
+                Some((child, operation)) => {
+                    path.push(*next);
+                    *next += 1;
+                    visitor.pre(
+                        child,
+                        Context {
+                            path: &path,
+                            parent: operation,
+                        },
+                    );
+                    stack.push((child, operation, 0));
+                }
+                None => {
+                    let parent = *parent;
+                    stack.pop();
+                    visitor.post(
+                        packet,
+                        Context {
+                            path: &path,
+                            parent,
+                        },
+                    );
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    /// Folds the tree into a single value. Like [`Packet::visit`], this
+    /// works on trees of any depth.
+    pub fn fold<F: Fold<V> + ?Sized>(&self, folder: &mut F) -> F::Output {
+        let mut path = vec![];
+        let mut stack: Vec<FoldFrame<V, F::Output>> = vec![];
+        let mut packet = self;
+        let mut parent = None;
+
+        loop {
+            // Go down the first sub-packets to a literal or an empty operator.
+            let mut output = match packet {
+                Packet::Literal { version, value } => folder.literal(
+                    *version,
+                    value,
+                    Context {
+                        path: &path,
+                        parent,
+                    },
+                ),
+                Packet::Operator {
+                    version,
+                    operation,
+                    packets,
+                } => {
+                    if let Some(first) = packets.first() {
+                        stack.push(FoldFrame {
+                            version: *version,
+                            operation: *operation,
+                            packets,
+                            parent,
+                            values: Vec::with_capacity(packets.len()),
+                        });
+                        path.push(0);
+                        parent = Some(*operation);
+                        packet = first;
+                        continue;
+                    }
+
+                    let context = Context {
+                        path: &path,
+                        parent,
+                    };
+                    folder.operator(*version, *operation, vec![], context)
+                }
+            };
+
+            // Hand the value up, folding every operator it completes, until
+            // an operator has another sub-packet to fold.
+            loop {
+                let mut frame = match stack.pop() {
+                    Some(frame) => frame,
+                    None => return output,
+                };
+                frame.values.push(output);
+                path.pop();
+
+                if let Some(next) = frame.packets.get(frame.values.len()) {
+                    path.push(frame.values.len());
+                    parent = Some(frame.operation);
+                    packet = next;
+                    stack.push(frame);
+                    break;
+                }
+
+                let context = Context {
+                    path: &path,
+                    parent: frame.parent,
+                };
+                output = folder.operator(frame.version, frame.operation, frame.values, context);
+            }
+        }
+    }
+
+    fn version(&self) -> u8 {
+        match self {
+            Packet::Literal { version, .. } | Packet::Operator { version, .. } => *version,
+        }
+    }
+}
+
+/// Drops the sub-packets a level at a time rather than recursively, so that
+/// deeply nested packets can't overflow the stack.
+impl<V> Drop for Packet<V> {
+    fn drop(&mut self) {
+        if let Packet::Operator { packets, .. } = self {
+            let mut pending = mem::take(packets);
+            while let Some(mut packet) = pending.pop() {
+                if let Packet::Operator { packets, .. } = &mut packet {
+                    pending.append(packets);
+                }
+            }
+        }
+    }
+}
+
+/// Sums the version numbers of every packet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionSum {
+    pub total: usize,
+}
+
+impl<V> Visitor<V> for VersionSum {
+    fn pre(&mut self, packet: &Packet<V>, _context: Context) {
+        self.total += packet.version() as usize;
+    }
+}
+
+/// Counts every packet, literals and operators alike.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeCount {
+    pub total: usize,
+}
+
+impl<V> Visitor<V> for NodeCount {
+    fn pre(&mut self, _packet: &Packet<V>, _context: Context) {
+        self.total += 1;
+    }
+}
+
+/// Finds the depth of the most deeply nested packet, where the outermost
+/// packet has depth 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaxDepth {
+    pub depth: usize,
+}
+
+impl<V> Visitor<V> for MaxDepth {
+    fn pre(&mut self, _packet: &Packet<V>, context: Context) {
+        self.depth = self.depth.max(context.depth());
+    }
+}
+
+/// Counts the operators using each operation, along with the literals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    pub operations: BTreeMap<Operation, usize>,
+    pub literals: usize,
+}
+
+impl<V> Visitor<V> for Histogram {
+    fn pre(&mut self, packet: &Packet<V>, _context: Context) {
+        match packet {
+            Packet::Literal { .. } => self.literals += 1,
+            Packet::Operator { operation, .. } => {
+                *self.operations.entry(*operation).or_default() += 1
+            }
+        }
+    }
+}
+
+/// Rewrites every operator whose sub-packets are all literals into a literal
+/// holding its value, keeping the operator's version. Operators that can't be
+/// evaluated, because they overflow or have the wrong number of sub-packets,
+/// are kept as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConstantFolder;
+
+impl<V: Value> Fold<V> for ConstantFolder {
+    type Output = Packet<V>;
+
+    fn literal(&mut self, version: u8, value: &V, _context: Context) -> Packet<V> {
+        Packet::Literal {
+            version,
+            value: value.clone(),
+        }
+    }
+
+    fn operator(
+        &mut self,
+        version: u8,
+        operation: Operation,
+        packets: Vec<Packet<V>>,
+        _context: Context,
+    ) -> Packet<V> {
+        let values = packets
+            .iter()
+            .map(|packet| match packet {
+                Packet::Literal { value, .. } => Some(value.clone()),
+                Packet::Operator { .. } => None,
+            })
+            .collect::<Option<Vec<V>>>();
+
+        match values.map(|values| apply(operation, values)) {
+            Some(Ok(value)) => Packet::Literal { version, value },
+            _ => Packet::Operator {
+                version,
+                operation,
+                packets,
+            },
+        }
+    }
+}
+
+impl<V: Value> Packet<V> {
+    /// Folds every constant sub-expression into a literal.
+    pub fn simplify(&self) -> Self {
+        self.fold(&mut ConstantFolder)
+    }
+}
+
+/// Sums the version numbers of every packet.
+#[aoc(day16, part1)]
+pub fn part1(input: &Packet) -> usize {
+    let mut sum = VersionSum::default();
+    input.visit(&mut sum);
+    sum.total
+}
+
+/// Why a packet could not be evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvaluateErrorKind {
+    /// The operation's result is too large for the value type.
+    Overflow(Operation),
+    /// A minimum or maximum has no sub-packets.
+    Empty(Operation),
+    /// A comparison has other than two sub-packets.
+    Comparison {
+        operation: Operation,
+        packets: usize,
+    },
+}
+
+/// A packet that could not be evaluated, along with where it is in the
+/// tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvaluateError {
+    /// The index of the sub-packet taken at each level on the way down from
+    /// the outermost packet.
+    pub path: Vec<usize>,
+    pub kind: EvaluateErrorKind,
+}
+
+impl fmt::Display for EvaluateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            EvaluateErrorKind::Overflow(operation) => write!(f, "{} overflowed", operation)?,
+            EvaluateErrorKind::Empty(operation) => write!(f, "{} of no sub-packets", operation)?,
+            EvaluateErrorKind::Comparison { operation, packets } => write!(
+                f,
+                "{} needs exactly 2 sub-packets, found {}",
+                operation, packets
+            )?,
+        }
+
+        write!(f, " at packet /{}", self.path.iter().join("/"))
+    }
+}
+
+impl Error for EvaluateError {}
+
+/// Applies an operation to the values of its sub-packets.
+fn apply<V: Value>(operation: Operation, values: Vec<V>) -> Result<V, EvaluateErrorKind> {
+    match operation {
+        Operation::Sum => values
+            .iter()
+            .try_fold(V::from(0), |acc, value| acc.checked_add(value))
+            .ok_or(EvaluateErrorKind::Overflow(operation)),
+        Operation::Product => values
+            .iter()
+            .try_fold(V::from(1), |acc, value| acc.checked_mul(value))
+            .ok_or(EvaluateErrorKind::Overflow(operation)),
+        Operation::Minimum => values
+            .into_iter()
+            .min()
+            .ok_or(EvaluateErrorKind::Empty(operation)),
+        Operation::Maximum => values
+            .into_iter()
+            .max()
+            .ok_or(EvaluateErrorKind::Empty(operation)),
+        Operation::GreaterThan | Operation::LessThan | Operation::EqualTo => match &values[..] {
+            [left, right] => {
+                let result = match operation {
+                    Operation::GreaterThan => left > right,
+                    Operation::LessThan => left < right,
+                    _ => left == right,
+                };
+                Ok(V::from(result as u8))
+            }
+            _ => Err(EvaluateErrorKind::Comparison {
+                operation,
+                packets: values.len(),
+            }),
+        },
+    }
+}
+
+struct Evaluator;
+
+impl<V: Value> Fold<V> for Evaluator {
+    type Output = Result<V, EvaluateError>;
+
+    fn literal(&mut self, _version: u8, value: &V, _context: Context) -> Self::Output {
+        Ok(value.clone())
+    }
+
+    fn operator(
+        &mut self,
+        _version: u8,
+        operation: Operation,
+        values: Vec<Self::Output>,
+        context: Context,
+    ) -> Self::Output {
+        let values = values.into_iter().collect::<Result<Vec<V>, _>>()?;
+        apply(operation, values).map_err(|kind| EvaluateError {
+            path: context.path.to_vec(),
+            kind,
+        })
+    }
+}
+
+/// Evaluates the expression represented by the packet, pointing out the
+/// packet that overflows or has the wrong number of sub-packets.
+pub fn evaluate_checked<V: Value>(packet: &Packet<V>) -> Result<V, EvaluateError> {
+    packet.fold(&mut Evaluator)
+}
+
+/// Evaluates the expression represented by the packet, returning `None` if
+/// it overflows or an operator has the wrong number of sub-packets.
+pub fn evaluate(packet: &Packet) -> Option<u64> {
+    evaluate_checked(packet).ok()
+}
+
+/// Evaluates the outermost packet.
+#[aoc(day16, part2)]
+pub fn part2(input: &Packet) -> eyre::Result<u64> {
+    Ok(evaluate_checked(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn literal(version: u8, value: u64) -> Packet {
+        Packet::Literal { version, value }
+    }
+
+    #[test]
+    fn test_parse_literal() -> eyre::Result<()> {
+        assert_eq!(parse("D2FE28")?, literal(6, 2021));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_operator_by_length() -> eyre::Result<()> {
+        let expected = Packet::Operator {
+            version: 1,
+            operation: Operation::LessThan,
+            packets: vec![literal(6, 10), literal(2, 20)],
+        };
+        assert_eq!(parse("38006F45291200")?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_operator_by_count() -> eyre::Result<()> {
+        let expected = Packet::Operator {
+            version: 7,
+            operation: Operation::Maximum,
+            packets: vec![literal(2, 1), literal(4, 2), literal(1, 3)],
+        };
+        assert_eq!(parse("EE00D40C823060")?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse("D2FG28").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 4));
+    }
+
+    /// Packs a string of bits into hex, padding it with zeros.
+    fn hex(bits: &str) -> String {
+        let mut padded = bits.replace(' ', "");
+        padded.push_str(&"0".repeat(padded.len().next_multiple_of(8) - padded.len()));
+        (0..padded.len())
+            .step_by(8)
+            .map(|i| {
+                format!(
+                    "{:02X}",
+                    u8::from_str_radix(&padded[i..i + 8], 2).unwrap_or(0)
+                )
+            })
+            .collect()
+    }
+
+    fn decode_hex(hex: &str) -> Result<Packet, DecodeError> {
+        let data = hex_string(hex).map(|(_, data)| data).unwrap_or_default();
+        decode(&data)
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let error = |offset, kind| Err(DecodeError { offset, kind });
+
+        assert_eq!(
+            decode_hex("D2FE"),
+            error(16, DecodeErrorKind::Truncated("literal group"))
+        );
+        assert_eq!(
+            decode_hex(&hex("000000 0 000000000001010 000100 00001")),
+            error(
+                22,
+                DecodeErrorKind::LengthOverrun {
+                    length: 10,
+                    consumed: 11
+                }
+            )
+        );
+        assert_eq!(
+            decode_hex(&hex(
+                "000101 1 00000000011 00010000001 00010000001 00010000001"
+            )),
+            error(
+                0,
+                DecodeErrorKind::Comparison {
+                    operation: Operation::GreaterThan,
+                    packets: 3
+                }
+            )
+        );
+        assert_eq!(
+            decode_hex(&hex(&format!("000100 10001{}00000", "10000".repeat(15)))),
+            error(6, DecodeErrorKind::LiteralOverflow)
+        );
+        assert_eq!(decode_hex("D2FE29"), error(23, DecodeErrorKind::Padding));
+        assert_eq!(decode_hex("D2FE2800"), Ok(literal(6, 2021)));
+        assert_eq!(
+            decode_hex("38"),
+            error(7, DecodeErrorKind::Truncated("sub-packet length"))
+        );
+
+        let nested = hex(&format!(
+            "{}000100 00001",
+            "000000 1 00000000001 ".repeat(3)
+        ));
+        let data = hex_string(&nested)
+            .map(|(_, data)| data)
+            .unwrap_or_default();
+        assert_eq!(
+            decode_with::<u64>(&data, &DecodeOptions { max_depth: 1 }),
+            error(36, DecodeErrorKind::TooDeep(1))
+        );
+        assert!(decode_with::<u64>(&data, &DecodeOptions { max_depth: 3 }).is_ok());
+    }
+
+    #[test]
+    fn test_parse_decode_error() {
+        let error = parse("D2FE29").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 6));
+        assert_eq!(error.message, "bit 23: non-zero padding");
+
+        let nested = hex(&format!(
+            "{}000100 00001",
+            "000000 1 00000000001 ".repeat(40_000)
+        ));
+        let error = parse(&nested).unwrap_err();
+        assert_eq!(error.column, 4626 / 4 + 1);
+        assert_eq!(
+            error.message,
+            "bit 4626: nested inside more than 256 operators"
+        );
+        assert!(Disassembly::new(&nested).is_err());
+    }
+
+    fn bit_string(bits: &[bool]) -> String {
+        bits.iter()
+            .map(|&bit| if bit { '1' } else { '0' })
+            .collect()
+    }
+
+    #[test]
+    fn test_stream() -> eyre::Result<()> {
+        let expected = ["D2FE28", "38006F45291200", "EE00D40C823060"]
+            .into_iter()
+            .map(parse)
+            .collect::<Result<Vec<Packet>, _>>()?;
+        let bits = expected
+            .iter()
+            .map(|packet| packet.to_bits(&EncodeOptions::default()))
+            .collect::<eyre::Result<Vec<_>>>()?
+            .concat();
+        let mut transmission = hex(&bit_string(&bits));
+        transmission.insert(4, '\n');
+
+        let mut stream = PacketStream::new(transmission.as_bytes());
+        assert_eq!(stream.next().transpose()?, Some(expected[0].clone()));
+        assert_eq!(stream.offset(), 21);
+        let rest = stream.collect::<Result<Vec<Packet>, _>>()?;
+        assert_eq!(rest, expected[1..]);
+
+        for transmission in ["8A004A801A8002F478", "A0016C880162017C3686B18A3D4780"] {
+            let packets =
+                PacketStream::new(transmission.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(packets, [parse(transmission)?]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_error() {
+        let mut stream = PacketStream::<_, u64>::new("D2 FE".as_bytes());
+        match stream.next() {
+            Some(Err(StreamError::Decode(error))) => {
+                assert_eq!(error.kind, DecodeErrorKind::Truncated("literal group"))
+            }
+            other => panic!("expected a decode error, got {:?}", other),
+        }
+        assert!(stream.next().is_none());
+
+        let mut stream = PacketStream::<_, u64>::new("D2XE28".as_bytes());
+        assert!(matches!(
+            stream.next(),
+            Some(Err(StreamError::InvalidHex {
+                position: 2,
+                byte: b'X'
+            }))
+        ));
+
+        assert!(PacketStream::<_, u64>::new("0000\n".as_bytes())
+            .next()
+            .is_none());
+
+        let nested = hex(&format!(
+            "{}000100 00001",
+            "000000 1 00000000001 ".repeat(3)
+        ));
+        let options = DecodeOptions { max_depth: 1 };
+        let mut stream = PacketStream::<_, u64>::with_options(nested.as_bytes(), options);
+        match stream.next() {
+            Some(Err(StreamError::Decode(error))) => {
+                assert_eq!(
+                    error,
+                    DecodeError {
+                        offset: 36,
+                        kind: DecodeErrorKind::TooDeep(1)
+                    }
+                )
+            }
+            other => panic!("expected a decode error, got {:?}", other),
+        }
+        assert_eq!(PacketStream::<_, u64>::new(nested.as_bytes()).count(), 1);
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        assert_eq!(part1(&parse("8A004A801A8002F478")?), 16);
+        assert_eq!(part1(&parse("620080001611562C8802118E34")?), 12);
+        assert_eq!(part1(&parse("C0015000016115A2E0802F182340")?), 23);
+        assert_eq!(part1(&parse("A0016C880162017C3686B18A3D4780")?), 31);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        assert_eq!(part2(&parse("C200B40A82")?)?, 3);
+        assert_eq!(part2(&parse("04005AC33890")?)?, 54);
+        assert_eq!(part2(&parse("880086C3E88112")?)?, 7);
+        assert_eq!(part2(&parse("CE00C43D881120")?)?, 9);
+        assert_eq!(part2(&parse("D8005AC2A8F0")?)?, 1);
+        assert_eq!(part2(&parse("F600BC2D8F")?)?, 0);
+        assert_eq!(part2(&parse("9C005AC2F8F0")?)?, 0);
+        assert_eq!(part2(&parse("9C0141080250320F1802104A08")?)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyses() -> eyre::Result<()> {
+        let packet = parse("9C0141080250320F1802104A08")?;
+
+        let mut count = NodeCount::default();
+        packet.visit(&mut count);
+        assert_eq!(count.total, 7);
+
+        let mut depth = MaxDepth::default();
+        packet.visit(&mut depth);
+        assert_eq!(depth.depth, 2);
+
+        let mut histogram = Histogram::default();
+        packet.visit(&mut histogram);
+        assert_eq!(histogram.literals, 4);
+        assert_eq!(
+            histogram.operations.into_iter().collect::<Vec<_>>(),
+            [
+                (Operation::Sum, 1),
+                (Operation::Product, 1),
+                (Operation::EqualTo, 1)
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_visitor_order() -> eyre::Result<()> {
+        #[derive(Default)]
+        struct Trace(Vec<String>);
+
+        impl Visitor for Trace {
+            fn pre(&mut self, packet: &Packet, context: Context) {
+                let parent = context.parent.map(|operation| operation.to_string());
+                self.0
+                    .push(format!("pre {} {:?} {}", context.depth(), parent, packet));
+            }
+
+            fn post(&mut self, packet: &Packet, context: Context) {
+                self.0.push(format!("post {:?} {}", context.path, packet));
+            }
+        }
+
+        let mut trace = Trace::default();
+        parse("38006F45291200")?.visit(&mut trace);
+        assert_eq!(
+            trace.0,
+            [
+                "pre 0 None 10 < 20",
+                "pre 1 Some(\"lt\") 10",
+                "post [0] 10",
+                "pre 1 Some(\"lt\") 20",
+                "post [1] 20",
+                "post [] 10 < 20",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep() -> eyre::Result<()> {
+        let mut packet = literal(1, 7);
+        for _ in 0..100_000 {
+            packet = Packet::Operator {
+                version: 1,
+                operation: Operation::Sum,
+                packets: vec![packet],
+            };
+        }
+
+        let mut depth = MaxDepth::default();
+        packet.visit(&mut depth);
+        assert_eq!(depth.depth, 100_000);
+        assert_eq!(part1(&packet), 100_001);
+        assert_eq!(evaluate_checked(&packet)?, 7);
+
+        let nested = |depth| {
+            hex(&format!(
+                "{}000100 00111",
+                "000000 1 00000000001 ".repeat(depth)
+            ))
+        };
+        let max_depth = DecodeOptions::default().max_depth;
+        let transmission = nested(max_depth);
+        let packet = PacketStream::<_, u64>::new(transmission.as_bytes())
+            .next()
+            .transpose()?
+            .unwrap_or_else(|| literal(0, 0));
+        assert_eq!(packet.clone(), packet);
+        let expected = format!("{}7{}", "sum(".repeat(max_depth), ")".repeat(max_depth));
+        assert_eq!(packet.to_string(), expected);
+        let options = EncodeOptions {
+            length_type: LengthType::Count,
+            ..EncodeOptions::default()
+        };
+        assert_eq!(packet.to_hex(&options)?, transmission);
+
+        let transmission = nested(40_000);
+        let mut stream = PacketStream::<_, u64>::new(transmission.as_bytes());
+        assert!(matches!(
+            stream.next(),
+            Some(Err(StreamError::Decode(DecodeError {
+                kind: DecodeErrorKind::TooDeep(256),
+                ..
+            })))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_simplify() {
+        let operator = |version, operation, packets| Packet::Operator {
+            version,
+            operation,
+            packets,
+        };
+        let sum = operator(3, Operation::Sum, vec![literal(0, 1), literal(0, 2)]);
+        assert_eq!(sum.simplify(), literal(3, 3));
+
+        let overflow = operator(
+            1,
+            Operation::Product,
+            vec![literal(0, u64::MAX), literal(0, 2)],
+        );
+        let nested = operator(2, Operation::Maximum, vec![sum, overflow.clone()]);
+        assert_eq!(
+            nested.simplify(),
+            operator(2, Operation::Maximum, vec![literal(3, 3), overflow])
+        );
+
+        let empty = operator(4, Operation::Minimum, vec![]);
+        assert_eq!(empty.simplify(), empty);
+    }
+
+    #[test]
+    fn test_encode() -> eyre::Result<()> {
+        let options = EncodeOptions::default();
+        assert_eq!(literal(6, 2021).to_hex(&options)?, "D2FE28");
+        assert_eq!(parse("38006F45291200")?.to_hex(&options)?, "38006F45291200");
+
+        let options = EncodeOptions {
+            length_type: LengthType::Count,
+            ..options
+        };
+        assert_eq!(parse("EE00D40C823060")?.to_hex(&options)?, "EE00D40C823060");
+
+        let bits = literal(0, 1).to_bits(&EncodeOptions {
+            literal_groups: 3,
+            ..options
+        })?;
+        assert_eq!(bits.len(), 6 + 3 * 5);
+
+        assert_eq!(literal_nibble(0x1234, 0), 4);
+        assert_eq!(literal_nibble(0x1234, 3), 1);
+        assert_eq!(literal_nibble(u64::MAX, 15), 0xF);
+        assert_eq!(literal_nibble(u64::MAX, 16), 0);
+        assert_eq!(literal_nibble(u64::MAX, usize::MAX / 4 + 1), 0);
+        assert_eq!(literal_nibble(u64::MAX, usize::MAX), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_encode_decode() -> eyre::Result<()> {
+        let transmissions = [
+            "8A004A801A8002F478",
+            "620080001611562C8802118E34",
+            "C0015000016115A2E0802F182340",
+            "A0016C880162017C3686B18A3D4780",
+            "9C0141080250320F1802104A08",
+        ];
+        for transmission in transmissions {
+            let packet = parse(transmission)?;
+            for length_type in [LengthType::Bits, LengthType::Count] {
+                let options = EncodeOptions {
+                    length_type,
+                    literal_groups: 2,
+                };
+                assert_eq!(parse(&packet.to_hex(&options)?)?, packet);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_error() {
+        let options = EncodeOptions::default();
+        assert!(literal(8, 0).to_hex(&options).is_err());
+
+        let comparison = Packet::Operator {
+            version: 0,
+            operation: Operation::LessThan,
+            packets: vec![literal(0, 0)],
+        };
+        assert!(comparison.to_hex(&options).is_err());
+
+        let packet = Packet::Operator {
+            version: 0,
+            operation: Operation::Sum,
+            packets: vec![literal(0, 0); 2048],
+        };
+        let options = EncodeOptions {
+            length_type: LengthType::Count,
+            literal_groups: 1,
+        };
+        assert!(packet.to_hex(&options).is_err());
+    }
+
+    fn packet_strategy() -> impl Strategy<Value = Packet> {
+        let literal = (0..8u8, any::<u64>()).prop_map(|(version, value)| literal(version, value));
+        literal.prop_recursive(4, 32, 4, |inner| {
+            let operation = (0..8u8).prop_filter_map("literal type", Operation::from_type);
+            (0..8u8, operation, prop::collection::vec(inner, 0..4))
+                .prop_filter(
+                    "comparisons need 2 sub-packets",
+                    |(_, operation, packets)| !operation.is_comparison() || packets.len() == 2,
+                )
+                .prop_map(|(version, operation, packets)| Packet::Operator {
+                    version,
+                    operation,
+                    packets,
+                })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(
+            packet in packet_strategy(),
+            count in any::<bool>(),
+            literal_groups in 0..=20usize,
+        ) {
+            let length_type = if count { LengthType::Count } else { LengthType::Bits };
+            let options = EncodeOptions { length_type, literal_groups };
+
+            let hex = packet.to_hex(&options).map_err(|e| TestCaseError::fail(e.to_string()))?;
+            prop_assert_eq!(parse(&hex), Ok(packet));
+        }
+
+        #[test]
+        fn test_stream_round_trip(packets in prop::collection::vec(packet_strategy(), 0..4)) {
+            let options = EncodeOptions::default();
+            let encoded = packets
+                .iter()
+                .map(|packet| packet.to_bits(&options))
+                .collect::<eyre::Result<Vec<_>>>()
+                .map_err(|e| TestCaseError::fail(e.to_string()))?;
+            // A final packet of nothing but zeros can't be told apart from
+            // padding.
+            prop_assume!(encoded.last().is_none_or(|bits| bits.contains(&true)));
+
+            let transmission = hex(&bit_string(&encoded.concat()));
+            let decoded = PacketStream::new(transmission.as_bytes()).collect::<Result<Vec<Packet>, _>>();
+            prop_assert_eq!(decoded.map_err(|e| e.to_string()), Ok(packets));
+        }
+        #[test]
+        fn test_simplify_preserves_value(packet in packet_strategy()) {
+            let simplified = packet.simplify();
+            prop_assert_eq!(evaluate(&simplified), evaluate(&packet));
+            if evaluate(&packet).is_some() {
+                let folded = matches!(simplified, Packet::Literal { .. });
+                prop_assert!(folded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_checked() {
+        let operator = |operation, packets| Packet::Operator {
+            version: 0,
+            operation,
+            packets,
+        };
+        let product = operator(
+            Operation::Product,
+            vec![literal(0, u64::MAX), literal(0, 2)],
+        );
+        let sum = operator(Operation::Sum, vec![literal(0, 1), product]);
+
+        let error = evaluate_checked(&sum).unwrap_err();
+        assert_eq!(error.path, [1]);
+        assert_eq!(error.kind, EvaluateErrorKind::Overflow(Operation::Product));
+        assert_eq!(error.to_string(), "product overflowed at packet /1");
+
+        let minimum = operator(Operation::Minimum, vec![]);
+        assert_eq!(
+            evaluate_checked(&operator(Operation::Sum, vec![literal(0, 1), minimum])),
+            Err(EvaluateError {
+                path: vec![1],
+                kind: EvaluateErrorKind::Empty(Operation::Minimum)
+            })
+        );
+
+        let equal = operator(Operation::EqualTo, vec![literal(0, 1)]);
+        assert_eq!(
+            evaluate_checked(&equal).map_err(|error| error.to_string()),
+            Err("eq needs exactly 2 sub-packets, found 1 at packet /".to_string())
+        );
+    }
+
+    #[test]
+    fn test_evaluate_big() -> eyre::Result<()> {
+        // 2^64 * 2^64, which neither fits in a u64 literal nor a u64 product.
+        let two_pow_64 = format!("000100 10001{}00000", "10000".repeat(15));
+        let transmission = hex(&format!("000001 1 00000000010 {0} {0}", two_pow_64));
+
+        assert!(parse(&transmission).is_err());
+        let packet = parse_big(&transmission)?;
+        assert_eq!(evaluate_checked(&packet)?, BigUint::from(1u8) << 128u8);
+        assert_eq!(
+            evaluate_checked(&parse_big("9C0141080250320F1802104A08")?)?,
+            BigUint::from(1u8)
+        );
+
+        let product = hex("000001 1 00000000010 000100 01111 000100 01111");
+        let product = parse_big(&product)?;
+        assert_eq!(product.to_string(), "15 * 15");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_disassemble() -> eyre::Result<()> {
+        let disassembly = Disassembly::new("38006F45291200")?;
+        assert_eq!(
+            disassembly.instructions[0],
+            Instruction {
+                offset: 0,
+                depth: 0,
+                version: 1,
+                packet_type: 6,
+                body: Body::Operator {
+                    operation: Operation::LessThan,
+                    length_type: LengthType::Bits,
+                    length: 27,
+                },
+            }
+        );
+        assert_eq!(
+            disassembly.to_string(),
+            "     0  v1 type 6 lt length type 0, 27 bits
+    22    v6 type 4 literal 10 (1 group)
+    33    v2 type 4 literal 20 (2 groups)
+"
+        );
+
+        let disassembly = Disassembly::new("EE00D40C823060")?;
+        assert_eq!(disassembly.instructions.len(), 4);
+        assert_eq!(disassembly.instructions[3].offset, 40);
+
+        assert!(Disassembly::new("38006F4529").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_infix() -> eyre::Result<()> {
+        let operator = |operation, packets| Packet::Operator {
+            version: 0,
+            operation,
+            packets,
+        };
+        let sum = operator(Operation::Sum, vec![literal(0, 1), literal(0, 2)]);
+        let greater = operator(Operation::GreaterThan, vec![literal(0, 3), literal(0, 4)]);
+        let product = operator(Operation::Product, vec![sum.clone(), greater.clone()]);
+        assert_eq!(product.to_string(), "(1 + 2) * (3 > 4)");
+
+        let nested = operator(Operation::Sum, vec![sum, literal(0, 5)]);
+        assert_eq!(nested.to_string(), "1 + 2 + 5");
+
+        let chained = operator(Operation::EqualTo, vec![greater, literal(0, 1)]);
+        assert_eq!(chained.to_string(), "(3 > 4) == 1");
+
+        assert_eq!(parse("EE00D40C823060")?.to_string(), "max(1, 2, 3)");
+        assert_eq!(
+            parse("9C0141080250320F1802104A08")?.to_string(),
+            "1 + 3 == 2 * 2"
+        );
+        assert_eq!(
+            operator(Operation::Sum, vec![literal(0, 7)]).to_string(),
+            "sum(7)"
+        );
+
+        Ok(())
+    }
 }
@@ -1,25 +1,37 @@
-use eyre::ContextCompat;
+//! Day 17: Trick Shot
+
+use crate::error::{empty_input, lines, ParseError};
 use itertools::iproduct;
 use regex::Regex;
 use std::{cmp::Ordering, iter};
 
-type Pair = (i16, i16);
+/// A position or velocity. Wider than the target's coordinates so probes
+/// can overshoot without overflowing.
+pub type Pair = (i32, i32);
 
-#[derive(Clone, Copy, Debug)]
-struct Target {
-    x1: i16,
-    x2: i16,
-    y1: i16,
-    y2: i16,
+/// The target area, inclusive on both ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub x1: i16,
+    pub x2: i16,
+    pub y1: i16,
+    pub y2: i16,
 }
 
 impl Target {
     fn contains(&self, (x, y): Pair) -> bool {
-        (self.x1..=self.x2).contains(&x) && (self.y1..=self.y2).contains(&y)
+        (self.x1.into()..=self.x2.into()).contains(&x)
+            && (self.y1.into()..=self.y2.into()).contains(&y)
     }
 
     fn below(&self, (_, y): Pair) -> bool {
-        y < self.y1
+        y < self.y1.into()
+    }
+
+    /// Every launch velocity that could possibly reach the target.
+    fn velocities(&self) -> impl Iterator<Item = Pair> {
+        let (x2, y1) = (i32::from(self.x2), i32::from(self.y1));
+        iproduct!(1..=x2, y1..=(1 - y1))
     }
 }
 
@@ -28,7 +40,8 @@ struct Probe {
     velocity: Pair,
 }
 
-fn simulate(starting_velocity: Pair) -> impl Iterator<Item = Pair> {
+/// Yields the positions of a probe launched with `starting_velocity`.
+pub fn simulate(starting_velocity: Pair) -> impl Iterator<Item = Pair> {
     iter::successors(
         Some(Probe {
             position: (0, 0),
@@ -51,25 +64,33 @@ fn simulate(starting_velocity: Pair) -> impl Iterator<Item = Pair> {
     .map(|probe| probe.position)
 }
 
+/// Parses the `target area: x=..., y=...` description.
 #[aoc_generator(day17)]
-fn generator(input: &str) -> eyre::Result<Target> {
+pub fn parse(input: &str) -> Result<Target, ParseError> {
     let re =
-        Regex::new(r"target area: x=(?P<x1>\d+)..(?P<x2>\d+), y=(?P<y1>-?\d+)..(?P<y2>-?\d+)")?;
-    let captures = re.captures(input).context("unable to match input")?;
-    let x1 = captures["x1"].parse()?;
-    let x2 = captures["x2"].parse()?;
-    let y1 = captures["y1"].parse()?;
-    let y2 = captures["y2"].parse()?;
+        Regex::new(r"target area: x=(?P<x1>\d+)\.\.(?P<x2>\d+), y=(?P<y1>-?\d+)\.\.(?P<y2>-?\d+)")
+            .expect("invalid target pattern");
+
+    let line = lines(17, input).next().ok_or_else(|| empty_input(17))?;
+    let captures = re
+        .captures(line.text())
+        .ok_or_else(|| line.error(line.text(), "expected a target area"))?;
+    let x1 = line.parse(&captures["x1"])?;
+    let x2 = line.parse(&captures["x2"])?;
+    let y1 = line.parse(&captures["y1"])?;
+    let y2 = line.parse(&captures["y2"])?;
 
     Ok(Target { x1, x2, y1, y2 })
 }
 
+/// Finds the highest point reached by any launch that hits the target.
 #[aoc(day17, part1)]
-fn part1(target: &Target) -> Option<i16> {
-    iproduct!((1..=target.x2), (target.y1..=(1 - target.y1)))
+pub fn part1(target: &Target) -> Option<i32> {
+    target
+        .velocities()
         .filter_map(|starting_velocity| {
             simulate(starting_velocity)
-                .scan(i16::MIN, |prev_max_y, position @ (_, y)| {
+                .scan(i32::MIN, |prev_max_y, position @ (_, y)| {
                     *prev_max_y = y.max(*prev_max_y);
                     Some((*prev_max_y, position))
                 })
@@ -80,9 +101,11 @@ fn part1(target: &Target) -> Option<i16> {
         .max()
 }
 
+/// Counts the initial velocities that hit the target.
 #[aoc(day17, part2)]
-fn part2(target: &Target) -> usize {
-    iproduct!((1..=target.x2), target.y1..=(1 - target.y1))
+pub fn part2(target: &Target) -> usize {
+    target
+        .velocities()
         .filter(|&starting_velocity| {
             simulate(starting_velocity)
                 .take_while(|&position| !target.below(position))
@@ -90,3 +113,55 @@ fn part2(target: &Target) -> usize {
         })
         .count()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "target area: x=20..30, y=-10..-5";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let expected = Target {
+            x1: 20,
+            x2: 30,
+            y1: -10,
+            y2: -5,
+        };
+        assert_eq!(parse(EXAMPLE)?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulate() -> eyre::Result<()> {
+        let target = parse(EXAMPLE)?;
+        for velocity in [(7, 2), (6, 3), (9, 0), (6, 9)] {
+            assert!(simulate(velocity)
+                .take_while(|&position| !target.below(position))
+                .any(|position| target.contains(position)));
+        }
+
+        assert!(!simulate((17, -4))
+            .take_while(|&position| !target.below(position))
+            .any(|position| target.contains(position)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let target = parse(EXAMPLE)?;
+        assert_eq!(part1(&target), Some(45));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let target = parse(EXAMPLE)?;
+        assert_eq!(part2(&target), 112);
+
+        Ok(())
+    }
+}
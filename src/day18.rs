@@ -1,23 +1,59 @@
-use eyre::bail;
-use itertools::Itertools;
-use std::{ops::Add, str::FromStr};
-
-#[derive(Clone, Debug, Default, PartialEq)]
-struct SnailNumber {
-    values: Vec<u8>,
+//! Day 18: Snailfish
+
+use crate::error::{lines, Line, ParseError};
+use eyre::{bail, ContextCompat};
+use rayon::prelude::*;
+use std::{fmt, ops::Add, str::FromStr};
+
+/// How deeply a regular number can be nested when read in. Depths are stored
+/// as `u8`, and this leaves room to pair any two numbers read in.
+pub const MAX_DEPTH: u8 = u8::MAX - 1;
+
+/// How many explodes and splits reducing a number may take. Large values take
+/// steps in proportion to their size to reduce, so this bounds the time spent
+/// on them.
+pub const MAX_REDUCE_STEPS: usize = 1_000_000;
+
+/// A snailfish number, stored as its regular numbers in order along with how
+/// deeply each is nested.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnailNumber {
+    values: Vec<u64>,
     depths: Vec<u8>,
 }
 
 impl SnailNumber {
-    fn explode(&mut self) -> bool {
-        let position = self.depths.iter().position(|&depth| depth == 5);
+    fn empty() -> Self {
+        SnailNumber {
+            values: vec![],
+            depths: vec![],
+        }
+    }
+
+    /// Explodes the leftmost pair of regular numbers nested inside four or
+    /// more pairs, returning the position of its left value. The first two
+    /// neighbouring values at the same depth are always such a pair. Fails,
+    /// leaving the number as it was, if a neighbour overflows.
+    fn explode(&mut self) -> eyre::Result<Option<usize>> {
+        let position = self
+            .depths
+            .windows(2)
+            .position(|pair| pair[0] > 4 && pair[0] == pair[1]);
         if let Some(index) = position {
-            if index > 0 {
-                self.values[index - 1] += self.values[index];
+            let left = match index.checked_sub(1) {
+                Some(previous) => Some(add_values(self.values[previous], self.values[index])?),
+                None => None,
+            };
+            let right = match self.values.get(index + 2) {
+                Some(&next) => Some(add_values(next, self.values[index + 1])?),
+                None => None,
+            };
+
+            if let Some(left) = left {
+                self.values[index - 1] = left;
             }
-
-            if index + 2 < self.values.len() {
-                self.values[index + 2] += self.values[index + 1];
+            if let Some(right) = right {
+                self.values[index + 2] = right;
             }
 
             self.values.remove(index);
@@ -25,14 +61,13 @@ impl SnailNumber {
 
             self.values[index] = 0;
             self.depths[index] -= 1;
-
-            true
-        } else {
-            false
         }
+
+        Ok(position)
     }
 
-    fn split(&mut self) -> bool {
+    /// Splits the leftmost value of 10 or more, returning its position.
+    fn split(&mut self) -> Option<usize> {
         let position = self.values.iter().position(|&value| value >= 10);
         if let Some(index) = position {
             let value = self.values[index];
@@ -44,125 +79,516 @@ impl SnailNumber {
             let right = value - left;
             self.values.insert(index + 1, right);
             self.depths.insert(index + 1, self.depths[index]);
+        }
+
+        position
+    }
 
-            true
-        } else {
-            false
+    /// Explodes and splits until neither applies. Fails if a value overflows
+    /// or the number isn't reduced within [`MAX_REDUCE_STEPS`], leaving the
+    /// number partly reduced.
+    pub fn reduce(&mut self) -> eyre::Result<()> {
+        if self.depths.iter().all(|&depth| depth <= 5) {
+            return self.reduce_shallow();
         }
+
+        let mut steps = 0;
+        while self.explode()?.is_some() || self.split().is_some() {
+            steps += 1;
+            check_steps(steps)?;
+        }
+
+        Ok(())
     }
 
-    fn reduce(&mut self) {
-        while self.explode() || self.split() {}
+    /// Reduces a number nested at most five pairs deep, such as the sum of
+    /// two reduced numbers, without rescanning or shifting values.
+    fn reduce_shallow(&mut self) -> eyre::Result<()> {
+        // Exploding never nests anything deeper, so every pair that needs to
+        // explode can be exploded in a single pass.
+        let mut exploded: Vec<(u64, u8)> = Vec::with_capacity(self.values.len());
+        let mut carry = 0;
+        let mut index = 0;
+        while index < self.values.len() {
+            let value = add_values(self.values[index], carry)?;
+            carry = 0;
+
+            if self.depths[index] == 5 {
+                if let Some((previous, _)) = exploded.last_mut() {
+                    *previous = add_values(*previous, value)?;
+                }
+                carry = self.values[index + 1];
+                exploded.push((0, 4));
+                index += 2;
+            } else {
+                exploded.push((value, self.depths[index]));
+                index += 1;
+            }
+        }
+
+        // Only a split can nest a pair deep enough to explode, and that pair
+        // explodes straight away. Its left value can push the value before
+        // it to 10 or more, which is then the leftmost value to split.
+        let mut pending = exploded;
+        pending.reverse();
+        let mut reduced: Vec<(u64, u8)> = Vec::with_capacity(pending.len() * 2);
+        let mut steps = 0;
+        while let Some((value, depth)) = pending.pop() {
+            if value < 10 {
+                reduced.push((value, depth));
+                continue;
+            }
+
+            steps += 1;
+            check_steps(steps)?;
+
+            let left = value / 2;
+            let right = value - left;
+            if depth < 4 {
+                pending.push((right, depth + 1));
+                pending.push((left, depth + 1));
+                continue;
+            }
+
+            if let Some((next, _)) = pending.last_mut() {
+                *next = add_values(*next, right)?;
+            }
+            pending.push((0, depth));
+            if let Some((previous, _)) = reduced.last_mut() {
+                *previous = add_values(*previous, left)?;
+                if *previous >= 10 {
+                    pending.extend(reduced.pop());
+                }
+            }
+        }
+
+        (self.values, self.depths) = reduced.into_iter().unzip();
+
+        Ok(())
     }
 
-    fn magnitude(&self) -> usize {
-        let mut values = self
-            .values
+    /// Yields each explode or split taken while reducing the number, along
+    /// with the number it leaves behind.
+    pub fn steps(self, options: ReduceOptions) -> Steps {
+        let limit = match (options.limit, options.first_only) {
+            (limit, false) => limit,
+            (limit, true) => Some(limit.map_or(1, |limit| limit.min(1))),
+        };
+
+        Steps {
+            number: self,
+            remaining: limit,
+        }
+    }
+
+    /// Pairs two numbers without reducing the result. Fails if a value would
+    /// be nested deeper than `u8` can hold.
+    pub fn join(&self, rhs: &Self) -> eyre::Result<Self> {
+        let values = [&self.values[..], &rhs.values].concat();
+        let depths = self
+            .depths
             .iter()
-            .copied()
-            .map(usize::from)
-            .collect::<Vec<_>>();
-        let mut depths = self.depths.clone();
-        let max_depth = depths.iter().max().copied().unwrap_or_default();
-
-        for depth in (1..=max_depth).rev() {
-            while let Some(index) = depths.iter().position(|&d| d == depth) {
-                let right = values.remove(index + 1);
-                depths.remove(index + 1);
-
-                depths[index] -= 1;
-                values[index] = values[index] * 3 + right * 2;
+            .chain(&rhs.depths)
+            .map(|depth| depth.checked_add(1))
+            .collect::<Option<_>>()
+            .with_context(|| format!("nested inside more than {} pairs", u8::MAX))?;
+
+        Ok(SnailNumber { values, depths })
+    }
+
+    /// Computes the magnitude of the number, or `None` if it doesn't fit in a
+    /// `u128`. Each value contributes at most `3^depth` times itself, so this
+    /// can only happen for numbers nested far deeper than reduced ones.
+    pub fn magnitude(&self) -> Option<u128> {
+        let mut stack: Vec<(u128, u8)> = vec![];
+        for (&value, &depth) in self.values.iter().zip(&self.depths) {
+            let mut element = (u128::from(value), depth);
+            while let Some(&(left, left_depth)) = stack.last() {
+                let (right, right_depth) = element;
+                if left_depth != right_depth || right_depth == 0 {
+                    break;
+                }
+
+                stack.pop();
+                let magnitude = left.checked_mul(3)?.checked_add(right.checked_mul(2)?)?;
+                element = (magnitude, right_depth - 1);
             }
+
+            stack.push(element);
         }
 
-        values[0]
+        Some(stack.first().map_or(0, |&(magnitude, _)| magnitude))
     }
 }
 
+fn check_steps(steps: usize) -> eyre::Result<()> {
+    if steps > MAX_REDUCE_STEPS {
+        bail!("not reduced within {} steps", MAX_REDUCE_STEPS);
+    }
+
+    Ok(())
+}
+
+fn add_values(left: u64, right: u64) -> eyre::Result<u64> {
+    left.checked_add(right)
+        .with_context(|| format!("{} + {} overflowed", left, right))
+}
+
+/// Adds and reduces two numbers, failing if the sum is nested too deeply or a
+/// value overflows while reducing.
 impl Add for SnailNumber {
-    type Output = SnailNumber;
+    type Output = eyre::Result<SnailNumber>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let mut values = self.values.clone();
-        values.extend_from_slice(&rhs.values);
+        &self + &rhs
+    }
+}
 
-        let mut depths = self.depths;
-        depths.extend_from_slice(&rhs.depths);
-        depths.iter_mut().for_each(|depth| *depth += 1);
+impl Add for &SnailNumber {
+    type Output = eyre::Result<SnailNumber>;
 
-        let mut number = SnailNumber { values, depths };
-        number.reduce();
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut number = self.join(rhs)?;
+        number.reduce()?;
 
-        number
+        Ok(number)
     }
 }
 
-#[derive(Debug, Default)]
-struct ParserState {
-    current_depth: u8,
-    values: Vec<u8>,
-    depths: Vec<u8>,
+/// A single reduction rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Explode,
+    Split,
 }
 
-impl From<ParserState> for SnailNumber {
-    fn from(ParserState { values, depths, .. }: ParserState) -> Self {
-        SnailNumber { values, depths }
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Explode => f.write_str("explode"),
+            Action::Split => f.write_str("split"),
+        }
     }
 }
 
-impl FromStr for SnailNumber {
-    type Err = eyre::Report;
+/// One step of reducing a number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub action: Action,
+    /// The position of the exploded pair's left value or the split value,
+    /// counting regular numbers from the left starting at 0.
+    pub position: usize,
+    /// The number after the step.
+    pub number: SnailNumber,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let state = s.chars().try_fold(ParserState::default(), |mut state, c| {
-            match c {
-                '[' => state.current_depth += 1,
-                ']' => state.current_depth -= 1,
-                ',' => {}
-                c if c.is_ascii_digit() => {
-                    state.values.push(c.to_digit(10).unwrap() as u8);
-                    state.depths.push(state.current_depth);
+/// How far [`SnailNumber::steps`] reduces a number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReduceOptions {
+    /// The most steps to take, or `None` to reduce fully.
+    pub limit: Option<usize>,
+    /// Stops after the first explode or split.
+    pub first_only: bool,
+}
+
+/// An iterator over the steps of reducing a number.
+#[derive(Clone, Debug)]
+pub struct Steps {
+    number: SnailNumber,
+    remaining: Option<usize>,
+}
+
+impl Steps {
+    /// The number after the steps taken so far.
+    pub fn number(&self) -> &SnailNumber {
+        &self.number
+    }
+
+    /// Whether no more rules apply, rather than the step limit being reached
+    /// or a value overflowing.
+    pub fn is_reduced(&self) -> bool {
+        let mut number = self.number.clone();
+        matches!(number.explode(), Ok(None)) && number.split().is_none()
+    }
+}
+
+/// Yields an error and stops if a value overflows.
+impl Iterator for Steps {
+    type Item = eyre::Result<Step>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        let (action, position) = match self.number.explode() {
+            Ok(Some(position)) => (Action::Explode, position),
+            Ok(None) => (Action::Split, self.number.split()?),
+            Err(error) => {
+                self.remaining = Some(0);
+                return Some(Err(error));
+            }
+        };
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+
+        Some(Ok(Step {
+            action,
+            position,
+            number: self.number.clone(),
+        }))
+    }
+}
+
+/// A snailfish number as an explicit binary tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnailTree {
+    Regular(u64),
+    Pair(Box<SnailTree>, Box<SnailTree>),
+}
+
+impl SnailTree {
+    pub fn pair(left: SnailTree, right: SnailTree) -> Self {
+        SnailTree::Pair(Box::new(left), Box::new(right))
+    }
+
+    fn flatten(&self, depth: u8, number: &mut SnailNumber) -> eyre::Result<()> {
+        match self {
+            SnailTree::Regular(value) => {
+                number.values.push(*value);
+                number.depths.push(depth);
+            }
+            SnailTree::Pair(left, right) => {
+                if depth == MAX_DEPTH {
+                    bail!("nested inside more than {} pairs", MAX_DEPTH);
+                }
+                let depth = depth + 1;
+                left.flatten(depth, number)?;
+                right.flatten(depth, number)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SnailTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnailTree::Regular(value) => write!(f, "{}", value),
+            SnailTree::Pair(left, right) => write!(f, "[{},{}]", left, right),
+        }
+    }
+}
+
+impl TryFrom<&SnailNumber> for SnailTree {
+    type Error = eyre::Report;
+
+    /// Rebuilds the tree, which fails only if the depths don't describe a tree
+    /// of pairs, as when [`SnailNumber::join`] nests pairs deeper than `u8`
+    /// can hold.
+    fn try_from(number: &SnailNumber) -> Result<Self, Self::Error> {
+        let mut stack: Vec<(SnailTree, u8)> = vec![];
+        for (&value, &depth) in number.values.iter().zip(&number.depths) {
+            let mut element = (SnailTree::Regular(value), depth);
+            while let Some((left, depth)) = stack.pop() {
+                if depth != element.1 || depth == 0 {
+                    stack.push((left, depth));
+                    break;
+                }
+
+                element = (SnailTree::pair(left, element.0), depth - 1);
+            }
+
+            stack.push(element);
+        }
+
+        match stack.pop() {
+            Some((tree, 0)) if stack.is_empty() => Ok(tree),
+            _ => bail!("snailfish number is not a tree of pairs"),
+        }
+    }
+}
+
+impl TryFrom<&SnailTree> for SnailNumber {
+    type Error = eyre::Report;
+
+    /// Flattens the tree, which must be a pair with no value nested deeper
+    /// than [`MAX_DEPTH`].
+    fn try_from(tree: &SnailTree) -> Result<Self, Self::Error> {
+        if let SnailTree::Regular(_) = tree {
+            bail!("expected a pair, found a regular number");
+        }
+
+        let mut number = SnailNumber::empty();
+        tree.flatten(0, &mut number)?;
+
+        Ok(number)
+    }
+}
+
+impl fmt::Display for SnailNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // How many elements each open pair has so far.
+        let mut pairs: Vec<u8> = vec![];
+        for (&value, &depth) in self.values.iter().zip(&self.depths) {
+            if pairs.last() == Some(&1) {
+                f.write_str(",")?;
+            }
+            while pairs.len() < usize::from(depth) {
+                f.write_str("[")?;
+                pairs.push(0);
+            }
+            write!(f, "{}", value)?;
+
+            while let Some(count) = pairs.last_mut() {
+                *count += 1;
+                if *count < 2 {
+                    break;
                 }
-                _ => bail!("unexpected {}", c),
+                f.write_str("]")?;
+                pairs.pop();
             }
+        }
 
-            Ok(state)
-        })?;
+        for _ in pairs {
+            f.write_str("]")?;
+        }
 
-        Ok(state.into())
+        Ok(())
     }
 }
 
+/// Parses a single snailfish number, checking that every pair has exactly
+/// two elements, that the outermost element is a pair and that no value is
+/// nested deeper than `max_depth`.
+fn snail_number(line: Line, max_depth: u8) -> Result<SnailNumber, ParseError> {
+    let text = line.text();
+    let mut number = SnailNumber::empty();
+    // How many elements each open pair has so far.
+    let mut pairs: Vec<u8> = vec![];
+    // Whether the last element read, a number or a pair, is complete.
+    let mut complete = false;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let span = &text[offset..offset + c.len_utf8()];
+        if complete && pairs.is_empty() {
+            return Err(line.error(&text[offset..], "unexpected text after number"));
+        }
+
+        match c {
+            '[' | '0'..='9' if complete => return Err(line.error(span, "expected ',' or ']'")),
+            ',' | ']' if !complete => return Err(line.error(span, "expected a number or pair")),
+            '[' => {
+                if pairs.len() == usize::from(max_depth) {
+                    let message = format!("nested inside more than {} pairs", max_depth);
+                    return Err(line.error(span, message));
+                }
+                pairs.push(0);
+            }
+            '0'..='9' => {
+                let end = text[offset..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .map_or(text.len(), |length| offset + length);
+                while chars.next_if(|&(next, _)| next < end).is_some() {}
+
+                let value = line.parse(&text[offset..end])?;
+                number.values.push(value);
+                number.depths.push(pairs.len() as u8);
+                complete = true;
+            }
+            ',' => match pairs.last() {
+                Some(1) => complete = false,
+                _ => return Err(line.error(span, "pair has more than two elements")),
+            },
+            ']' => {
+                if pairs.pop() != Some(2) {
+                    return Err(line.error(span, "pair has only one element"));
+                }
+            }
+            _ => return Err(line.error(span, "unexpected character")),
+        }
+
+        if complete {
+            if let Some(count) = pairs.last_mut() {
+                *count += 1;
+            }
+        }
+    }
+
+    if !pairs.is_empty() {
+        return Err(line.end_of_input("unclosed bracket"));
+    }
+    if !complete {
+        return Err(line.end_of_input("expected a number or pair"));
+    }
+    if number.depths == [0] {
+        return Err(line.error(text, "expected a pair"));
+    }
+
+    Ok(number)
+}
+
+impl FromStr for SnailNumber {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        snail_number(Line::new(18, 1, s), MAX_DEPTH)
+    }
+}
+
+/// The snailfish numbers, in order.
+pub type Input = Vec<SnailNumber>;
+
+/// Parses the homework assignment, one snailfish number per line.
 #[aoc_generator(day18)]
-fn generator(input: &str) -> eyre::Result<Vec<SnailNumber>> {
-    input.lines().map(str::parse).collect()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    lines(18, input)
+        .map(|line| snail_number(line, MAX_DEPTH))
+        .collect()
 }
 
+/// Adds up every number in order and computes the magnitude of the sum.
 #[aoc(day18, part1)]
-fn part1(input: &[SnailNumber]) -> Option<usize> {
-    input
-        .to_vec()
-        .into_iter()
-        .reduce(|acc, number| acc + number)
-        .map(|number| number.magnitude())
+pub fn part1(input: &[SnailNumber]) -> eyre::Result<u128> {
+    let (first, rest) = input.split_first().context("no numbers to add")?;
+    let sum = rest
+        .iter()
+        .try_fold(first.clone(), |acc, number| &acc + number)?;
+
+    sum.magnitude().context("magnitude overflowed")
 }
 
+/// Finds the largest magnitude from adding any two different numbers.
 #[aoc(day18, part2)]
-fn part2(input: &[SnailNumber]) -> Option<usize> {
-    input
-        .to_vec()
-        .into_iter()
-        .permutations(2)
-        .map(|numbers| {
-            let result = numbers[0].clone() + numbers[1].clone();
-            result.magnitude()
+pub fn part2(input: &[SnailNumber]) -> eyre::Result<u128> {
+    (0..input.len())
+        .into_par_iter()
+        .flat_map_iter(|left| {
+            (0..input.len())
+                .filter(move |&right| right != left)
+                .map(move |right| (&input[left] + &input[right]).map(|sum| sum.magnitude()))
         })
-        .max()
+        .try_reduce(|| None, |left, right| Ok(left.max(right)))?
+        .context("fewer than two numbers to add")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use eyre::ContextCompat;
+    use proptest::prelude::*;
+
+    fn tree_strategy() -> impl Strategy<Value = SnailTree> {
+        let leaf = (0u64..30).prop_map(SnailTree::Regular);
+        let inner = leaf.prop_recursive(3, 16, 2, |inner| {
+            (inner.clone(), inner).prop_map(|(left, right)| SnailTree::pair(left, right))
+        });
+
+        (inner.clone(), inner).prop_map(|(left, right)| SnailTree::pair(left, right))
+    }
 
     #[test]
     fn test_parser() -> eyre::Result<()> {
@@ -176,31 +602,138 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_multiple_digits() -> eyre::Result<()> {
+        let actual: SnailNumber = "[[15,0],123]".parse()?;
+        let expected = SnailNumber {
+            values: vec![15, 0, 123],
+            depths: vec![2, 2, 1],
+        };
+        assert_eq!(actual, expected);
+
+        let large: SnailNumber = "[18446744073709551615,1]".parse()?;
+        assert_eq!(large.values, [u64::MAX, 1]);
+
+        let error = "[18446744073709551616,1]"
+            .parse::<SnailNumber>()
+            .unwrap_err();
+        assert_eq!(error.column, 2);
+        assert_eq!(error.text, "18446744073709551616");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |s: &str| s.parse::<SnailNumber>().unwrap_err();
+        let cases = [
+            ("[1,2,3]", 5, "pair has more than two elements"),
+            ("[[1],2]", 4, "pair has only one element"),
+            ("[1,[2,3]", 9, "unclosed bracket"),
+            ("[1,2]]", 6, "unexpected text after number"),
+            ("[1,2][3,4]", 6, "unexpected text after number"),
+            ("[1 2]", 3, "unexpected character"),
+            ("[1[2,3]]", 3, "expected ',' or ']'"),
+            ("[,2]", 2, "expected a number or pair"),
+            ("[1,]", 4, "expected a number or pair"),
+            ("", 1, "expected a number or pair"),
+            ("7", 1, "expected a pair"),
+        ];
+
+        for (input, column, message) in cases {
+            let error = error(input);
+            assert_eq!(
+                (error.column, error.message.as_str()),
+                (column, message),
+                "{}",
+                input
+            );
+        }
+
+        let deep = format!("{}1{}", "[".repeat(255), ",1]".repeat(255));
+        let error = error(&deep);
+        assert_eq!(error.column, 255);
+        assert_eq!(error.message, "nested inside more than 254 pairs");
+    }
+
+    #[test]
+    fn test_display() -> eyre::Result<()> {
+        for text in [
+            "[1,2]",
+            "[[1,[2,3]],[[4,5],6]]",
+            "[[[[0,7],4],[15,[0,13]]],[1,1]]",
+            "[7,[6,[5,[4,[3,2]]]]]",
+        ] {
+            assert_eq!(text.parse::<SnailNumber>()?.to_string(), text);
+        }
+
+        let left: SnailNumber = "[[[[4,3],4],4],[7,[[8,4],9]]]".parse()?;
+        let right: SnailNumber = "[1,1]".parse()?;
+        assert_eq!(
+            (left + right)?.to_string(),
+            "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tree() -> eyre::Result<()> {
+        let number: SnailNumber = "[[1,12],3]".parse()?;
+        let tree = SnailTree::try_from(&number)?;
+        let expected = SnailTree::pair(
+            SnailTree::pair(SnailTree::Regular(1), SnailTree::Regular(12)),
+            SnailTree::Regular(3),
+        );
+        assert_eq!(tree, expected);
+        assert_eq!(tree.to_string(), "[[1,12],3]");
+        assert_eq!(SnailNumber::try_from(&tree)?, number);
+
+        assert!(SnailNumber::try_from(&SnailTree::Regular(3)).is_err());
+        let large = SnailTree::pair(SnailTree::Regular(u64::MAX), SnailTree::Regular(0));
+        assert_eq!(SnailNumber::try_from(&large)?.values, [u64::MAX, 0]);
+
+        let deep = (0..256).fold(SnailTree::Regular(0), |tree, _| {
+            SnailTree::pair(tree, SnailTree::Regular(1))
+        });
+        assert!(SnailNumber::try_from(&deep).is_err());
+
+        let deep: SnailNumber = format!("{}1{}", "[".repeat(254), ",1]".repeat(254)).parse()?;
+        let pair: SnailNumber = "[1,1]".parse()?;
+        let deeper = deep.join(&pair)?;
+        assert!(SnailTree::try_from(&deeper).is_ok());
+        assert!(deeper.to_string().starts_with(&"[".repeat(255)));
+        let error = deeper.join(&pair).err().context("joined too deeply")?;
+        assert_eq!(error.to_string(), "nested inside more than 255 pairs");
+
+        Ok(())
+    }
+
     #[test]
     fn test_expode() -> eyre::Result<()> {
         let expected: SnailNumber = "[[[[0,9],2],3],4]".parse()?;
         let mut number: SnailNumber = "[[[[[9,8],1],2],3],4]".parse()?;
-        assert!(number.explode());
+        assert_eq!(number.explode()?, Some(0));
         assert_eq!(number, expected);
 
         let expected: SnailNumber = "[7,[6,[5,[7,0]]]]".parse()?;
         let mut number: SnailNumber = "[7,[6,[5,[4,[3,2]]]]]".parse()?;
-        assert!(number.explode());
+        assert_eq!(number.explode()?, Some(4));
         assert_eq!(number, expected);
 
         let expected: SnailNumber = "[[6,[5,[7,0]]],3]".parse()?;
         let mut number: SnailNumber = "[[6,[5,[4,[3,2]]]],1]".parse()?;
-        assert!(number.explode());
+        assert_eq!(number.explode()?, Some(3));
         assert_eq!(number, expected);
 
         let expected: SnailNumber = "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]".parse()?;
         let mut number: SnailNumber = "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]".parse()?;
-        assert!(number.explode());
+        assert_eq!(number.explode()?, Some(3));
         assert_eq!(number, expected);
 
         let expected: SnailNumber = "[[3,[2,[8,0]]],[9,[5,[7,0]]]]".parse()?;
         let mut number: SnailNumber = "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]".parse()?;
-        assert!(number.explode());
+        assert_eq!(number.explode()?, Some(7));
         assert_eq!(number, expected);
 
         Ok(())
@@ -210,17 +743,90 @@ mod tests {
     fn test_split() -> eyre::Result<()> {
         let expected: SnailNumber = "[[5,5],1]".parse()?;
         let mut number: SnailNumber = "[10,1]".parse()?;
-        assert!(number.split());
+        assert_eq!(number.split(), Some(0));
         assert_eq!(number, expected);
 
         Ok(())
     }
 
+    #[test]
+    fn test_steps() -> eyre::Result<()> {
+        let left: SnailNumber = "[[[[4,3],4],4],[7,[[8,4],9]]]".parse()?;
+        let right: SnailNumber = "[1,1]".parse()?;
+        let number = left.join(&right)?;
+        assert_eq!(number.to_string(), "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+
+        let steps = number
+            .clone()
+            .steps(ReduceOptions::default())
+            .map(|step| step.map(|step| (step.action, step.position, step.number.to_string())))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let expected = [
+            (Action::Explode, 0, "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]"),
+            (Action::Explode, 4, "[[[[0,7],4],[15,[0,13]]],[1,1]]"),
+            (Action::Split, 3, "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]"),
+            (Action::Split, 6, "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]"),
+            (Action::Explode, 6, "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"),
+        ];
+        let expected =
+            expected.map(|(action, position, number)| (action, position, number.to_string()));
+        assert_eq!(steps, expected);
+
+        let options = ReduceOptions {
+            limit: Some(3),
+            ..ReduceOptions::default()
+        };
+        let mut steps = number.clone().steps(options);
+        assert_eq!(steps.by_ref().count(), 3);
+        assert!(!steps.is_reduced());
+        assert_eq!(steps.number().to_string(), expected[2].2);
+
+        let options = ReduceOptions {
+            limit: Some(3),
+            first_only: true,
+        };
+        let mut steps = number.steps(options);
+        assert_eq!(steps.by_ref().count(), 1);
+        assert_eq!(steps.number().to_string(), expected[0].2);
+
+        let mut steps = "[[1,2],3]"
+            .parse::<SnailNumber>()?
+            .steps(ReduceOptions::default());
+        assert!(steps.next().is_none());
+        assert!(steps.is_reduced());
+
+        let steps = "[[[[[9,8],1],2],3],4]"
+            .parse::<SnailNumber>()?
+            .steps(ReduceOptions::default())
+            .map(|step| step.map(|step| (step.action, step.position, step.number.to_string())))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let expected = [(Action::Explode, 0, "[[[[0,9],2],3],4]".to_string())];
+        assert_eq!(steps, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reduce_deep() -> eyre::Result<()> {
+        let mut number: SnailNumber = "[[[[[[1,2],3],4],5],6],7]".parse()?;
+        number.reduce()?;
+        assert_eq!(number.to_string(), "[[[[0,9],5],6],7]");
+
+        let deep = format!("{}1{}", "[".repeat(254), ",1]".repeat(254));
+        let mut number: SnailNumber = deep.parse()?;
+        assert_eq!(number.magnitude(), None);
+        number.reduce()?;
+        assert!(number.depths.iter().all(|&depth| depth <= 4));
+        assert!(number.magnitude().is_some());
+
+        Ok(())
+    }
+
     #[test]
     fn test_add() -> eyre::Result<()> {
         let left: SnailNumber = "[[[[4,3],4],4],[7,[[8,4],9]]]".parse()?;
         let right: SnailNumber = "[1,1]".parse()?;
-        let actual = left + right;
+        let actual = (left + right)?;
         let expected: SnailNumber = "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".parse()?;
 
         assert_eq!(actual, expected);
@@ -228,6 +834,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_overflow() -> eyre::Result<()> {
+        let text = "[18446744073709551615,[[[[1,1],1],1],1]]";
+        let mut number: SnailNumber = text.parse()?;
+        assert!(number.explode().is_err());
+        assert_eq!(number.to_string(), text);
+        assert!(number.reduce().is_err());
+
+        let left: SnailNumber = "[1,18446744073709551615]".parse()?;
+        let right: SnailNumber = "[[[[1,1],1],1],1]".parse()?;
+        let error = (&left + &right).err().context("sum overflowed")?;
+        assert_eq!(error.to_string(), "18446744073709551615 + 1 overflowed");
+        assert!(part1(&[left.clone(), right.clone()]).is_err());
+        assert!(part2(&[left, right]).is_err());
+
+        let large: SnailNumber = "[18446744073709551615,0]".parse()?;
+        let error = (&large + &"[1,1]".parse()?)
+            .err()
+            .context("huge value reduced")?;
+        assert_eq!(error.to_string(), "not reduced within 1000000 steps");
+
+        let mut steps = text.parse::<SnailNumber>()?.steps(ReduceOptions::default());
+        assert!(steps.next().is_some_and(|step| step.is_err()));
+        assert!(steps.next().is_none());
+        assert!(!steps.is_reduced());
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_example() -> eyre::Result<()> {
         let input = "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]
@@ -240,11 +875,11 @@ mod tests {
 [1,[[[9,3],9],[[9,0],[0,7]]]]
 [[[5,[7,4]],7],1]
 [[[[4,2],2],6],[8,7]]";
-        let numbers = generator(input)?;
-        let actual = numbers
-            .into_iter()
-            .reduce(|acc, number| acc + number)
-            .unwrap();
+        let numbers = parse(input)?;
+        let actual = numbers[1..]
+            .iter()
+            .cloned()
+            .try_fold(numbers[0].clone(), |acc, number| acc + number)?;
         let expected: SnailNumber =
             "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]".parse()?;
 
@@ -256,24 +891,70 @@ mod tests {
     #[test]
     fn test_magnitude() -> eyre::Result<()> {
         let number: SnailNumber = "[[1,2],[[3,4],5]]".parse()?;
-        assert_eq!(number.magnitude(), 143);
+        assert_eq!(number.magnitude(), Some(143));
 
         let number: SnailNumber = "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".parse()?;
-        assert_eq!(number.magnitude(), 1384);
+        assert_eq!(number.magnitude(), Some(1384));
 
         let number: SnailNumber = "[[[[1,1],[2,2]],[3,3]],[4,4]]".parse()?;
-        assert_eq!(number.magnitude(), 445);
+        assert_eq!(number.magnitude(), Some(445));
 
         let number: SnailNumber = "[[[[3,0],[5,3]],[4,4]],[5,5]]".parse()?;
-        assert_eq!(number.magnitude(), 791);
+        assert_eq!(number.magnitude(), Some(791));
 
         let number: SnailNumber = "[[[[5,0],[7,4]],[5,5]],[6,6]]".parse()?;
-        assert_eq!(number.magnitude(), 1137);
+        assert_eq!(number.magnitude(), Some(1137));
 
         let number: SnailNumber =
             "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]".parse()?;
-        assert_eq!(number.magnitude(), 3488);
+        assert_eq!(number.magnitude(), Some(3488));
+
+        let number: SnailNumber = "[[[[4294967295,4294967295],1],2],3]".parse()?;
+        assert_eq!(number.magnitude(), Some(4294967295 * 135 + 36));
+
+        Ok(())
+    }
+
+    const HOMEWORK: &str = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(HOMEWORK)?;
+        assert_eq!(part1(&input)?, 4140);
 
         Ok(())
     }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(HOMEWORK)?;
+        assert_eq!(part2(&input)?, 3993);
+
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_reduce_matches_steps(left in tree_strategy(), right in tree_strategy()) {
+            let left = SnailNumber::try_from(&left).map_err(|e| TestCaseError::fail(e.to_string()))?;
+            let right = SnailNumber::try_from(&right).map_err(|e| TestCaseError::fail(e.to_string()))?;
+            let number = left.join(&right).map_err(|e| TestCaseError::fail(e.to_string()))?;
+
+            let mut steps = number.clone().steps(ReduceOptions::default());
+            steps.by_ref().for_each(drop);
+
+            let mut reduced = number;
+            reduced.reduce().map_err(|e| TestCaseError::fail(e.to_string()))?;
+            prop_assert_eq!(&reduced, steps.number());
+        }
+    }
 }
@@ -1,200 +1,1190 @@
-use eyre::ContextCompat;
+//! Day 19: Beacon Scanner
+
+use crate::{
+    error::{empty_input, lines, Line, ParseError},
+    params::define_params,
+    registry::{Answer, IntoAnswer},
+};
+use eyre::{bail, ContextCompat, WrapErr};
 use itertools::{iproduct, Itertools};
-use nalgebra::{point, Matrix4, Point3, Transform3, Vector3};
-use std::collections::{HashMap, HashSet, VecDeque};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt,
+    io::Write,
+    ops::Mul,
+    str::FromStr,
+};
 
-type Point = Point3<f64>;
+/// A position relative to a scanner, or to scanner 0 once aligned.
+pub type Point = [i64; 3];
 
-#[derive(Debug, Default, Clone)]
-struct Scanner {
-    points: Vec<Point>,
-    unique_distances: Vec<HashSet<u32>>,
+fn add(left: Point, right: Point) -> Point {
+    [0, 1, 2].map(|axis| left[axis] + right[axis])
 }
 
-impl Scanner {
-    fn find_transform(&self, other: &Scanner) -> Option<Transform3<f64>> {
-        let overlaps = self.overlaps(other).take(4).collect::<Vec<_>>();
-        if overlaps.len() < 4 {
-            return None;
+fn sub(left: Point, right: Point) -> Point {
+    [0, 1, 2].map(|axis| left[axis] - right[axis])
+}
+
+/// The squared distance between two points.
+fn distance(left: Point, right: Point) -> u128 {
+    sub(left, right)
+        .iter()
+        .map(|&delta| u128::from(delta.unsigned_abs()).pow(2))
+        .sum()
+}
+
+/// How beacons are compared between scanners to find candidate matches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Fingerprint {
+    /// The distances from a beacon to each other beacon, keeping only those
+    /// that are unique within the scanner.
+    #[default]
+    Distances,
+    /// The side lengths of every triangle a beacon forms with two others.
+    /// Slower, but tells beacons apart in sparse or symmetric scans.
+    Triangles,
+}
+
+impl FromStr for Fingerprint {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "distances" => Ok(Fingerprint::Distances),
+            "triangles" => Ok(Fingerprint::Triangles),
+            _ => bail!("expected distances or triangles"),
         }
+    }
+}
 
-        let self_columns = overlaps
-            .iter()
-            .map(|&(i, _)| self.points[i].to_homogeneous())
-            .collect::<Vec<_>>();
-        let mut self_matrix = Matrix4::from_columns(&self_columns);
-        if !self_matrix.try_inverse_mut() {
-            return None;
+/// Part of a beacon's fingerprint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Signature {
+    Distance(u128),
+    /// The two sides touching the beacon, shortest first, then the third.
+    Triangle([u128; 3]),
+}
+
+define_params! {
+    /// How scanners are matched up with each other.
+    pub struct MatchConfig {
+        /// How many beacons two scanners must both detect to be aligned, at
+        /// least two to fix a rotation and a translation.
+        pub min_shared_beacons: usize = 12,
+        /// How beacons are compared between scanners.
+        pub fingerprint: Fingerprint = Fingerprint::Distances,
+        /// How many pairs of beacons with matching fingerprints are tried as
+        /// the same beacon before giving up on two scanners. Each try checks
+        /// all 24 rotations, so fewer tries rule out scanners that don't
+        /// overlap sooner, but an overlap is missed if every pair tried is a
+        /// false match. Four is enough for the puzzle inputs, raise it if
+        /// scanners are left out.
+        pub candidates: usize = 4,
+    }
+}
+
+impl MatchConfig {
+    /// Checks that scanners can be aligned as configured.
+    pub fn validate(&self) -> eyre::Result<()> {
+        if self.min_shared_beacons < 2 {
+            bail!(
+                "min_shared_beacons must be at least 2, found {}",
+                self.min_shared_beacons
+            );
+        }
+        if self.candidates == 0 {
+            bail!("candidates must be at least 1");
         }
 
-        let other_columns = overlaps
-            .iter()
-            .map(|&(_, j)| other.points[j].to_homogeneous())
-            .collect::<Vec<_>>();
-        let other_matrix = Matrix4::from_columns(&other_columns);
-
-        let transform_matrix = other_matrix * self_matrix;
-        let transform = Transform3::from_matrix_unchecked(transform_matrix);
-        Some(transform)
-    }
-
-    fn overlaps<'a>(&'a self, other: &'a Scanner) -> impl Iterator<Item = (usize, usize)> + 'a {
-        iproduct!(
-            self.unique_distances.iter().enumerate(),
-            other.unique_distances.iter().enumerate()
-        )
-        .filter_map(
-            |((self_index, self_distances), (other_index, other_distances))| {
-                let overlap_distances = self_distances & other_distances;
-                if overlap_distances.len() >= 11 {
-                    Some((self_index, other_index))
-                } else {
-                    None
-                }
-            },
-        )
+        Ok(())
     }
 }
 
-impl FromIterator<Point> for Scanner {
-    fn from_iter<T: IntoIterator<Item = Point>>(iter: T) -> Self {
-        let points = iter.into_iter().collect::<Vec<Point>>();
-        let distances = points.iter().enumerate().tuple_combinations().fold(
-            HashMap::<(usize, u32), HashSet<usize>>::new(),
-            |mut acc, ((left_index, left), (right_index, right))| {
-                let distance = (left - right).norm_squared() as u32;
-                acc.entry((left_index, distance))
-                    .or_default()
-                    .insert(right_index);
-                acc.entry((right_index, distance))
-                    .or_default()
-                    .insert(left_index);
-                acc
-            },
-        );
+/// One of the 24 ways a scanner can be facing, as a signed permutation
+/// matrix with determinant 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rotation([[i64; 3]; 3]);
 
-        let unique_distances = distances.iter().fold(
-            vec![HashSet::new(); points.len()],
-            |mut acc, (&(i, d), v)| {
-                if v.len() == 1 {
-                    acc[i].insert(d);
-                }
+impl Rotation {
+    pub const IDENTITY: Rotation = Rotation([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
 
-                acc
-            },
-        );
+    /// Every rotation, starting with the identity.
+    pub fn all() -> impl Iterator<Item = Rotation> {
+        iproduct!((0..3).permutations(3), 0..8).filter_map(|(axes, signs)| {
+            let mut matrix = [[0; 3]; 3];
+            for (row, &axis) in axes.iter().enumerate() {
+                matrix[row][axis] = if signs & (1 << row) == 0 { 1 } else { -1 };
+            }
 
-        Self {
-            points,
-            unique_distances,
-        }
+            let rotation = Rotation(matrix);
+            (rotation.determinant() == 1).then_some(rotation)
+        })
+    }
+
+    pub fn matrix(&self) -> [[i64; 3]; 3] {
+        self.0
+    }
+
+    /// The opposite rotation.
+    pub fn transpose(&self) -> Rotation {
+        Rotation([0, 1, 2].map(|row| [0, 1, 2].map(|column| self.0[column][row])))
+    }
+
+    fn determinant(&self) -> i64 {
+        let [a, b, c] = self.0;
+        a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+            + a[2] * (b[0] * c[1] - b[1] * c[0])
     }
 }
 
-struct Input {
-    scanners: Vec<Scanner>,
-    transforms: Vec<Transform3<f64>>,
+impl Mul<Point> for Rotation {
+    type Output = Point;
+
+    fn mul(self, point: Point) -> Point {
+        self.0.map(|row| (0..3).map(|i| row[i] * point[i]).sum())
+    }
 }
 
-#[aoc_generator(day19)]
-fn generator(input: &str) -> eyre::Result<Input> {
-    let scanners: Vec<Scanner> = input
-        .lines()
-        .try_fold::<_, _, eyre::Result<Vec<Vec<Point>>>>(vec![], |mut acc, line| {
-            if line.is_empty() {
-                return Ok(acc);
+impl Mul for Rotation {
+    type Output = Rotation;
+
+    fn mul(self, rhs: Rotation) -> Rotation {
+        Rotation(self.0.map(|row| {
+            let mut result = [0; 3];
+            for (column, value) in result.iter_mut().enumerate() {
+                *value = (0..3).map(|i| row[i] * rhs.0[i][column]).sum();
             }
+            result
+        }))
+    }
+}
 
-            if line.starts_with("---") {
-                acc.push(vec![]);
-                return Ok(acc);
+/// A rotation followed by a translation, taking points from one scanner's
+/// coordinates into another's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub translation: Point,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        rotation: Rotation::IDENTITY,
+        translation: [0; 3],
+    };
+
+    /// The position of the transformed scanner in the target coordinates.
+    pub fn origin(&self) -> Point {
+        self.translation
+    }
+
+    /// The transform back from the target coordinates.
+    pub fn inverse(&self) -> Transform {
+        let rotation = self.rotation.transpose();
+        Transform {
+            rotation,
+            translation: (rotation * self.translation).map(|n| -n),
+        }
+    }
+}
+
+impl Mul<Point> for Transform {
+    type Output = Point;
+
+    fn mul(self, point: Point) -> Point {
+        add(self.rotation * point, self.translation)
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    /// Applies `rhs` and then `self`.
+    fn mul(self, rhs: Transform) -> Transform {
+        Transform {
+            rotation: self.rotation * rhs.rotation,
+            translation: self * rhs.translation,
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<Transform> for nalgebra::Matrix4<f64> {
+    fn from(transform: Transform) -> Self {
+        let mut matrix = nalgebra::Matrix4::identity();
+        for row in 0..3 {
+            for column in 0..3 {
+                matrix[(row, column)] = transform.rotation.0[row][column] as f64;
             }
+            matrix[(row, 3)] = transform.translation[row] as f64;
+        }
+
+        matrix
+    }
+}
 
-            let point = Point::from(Vector3::from_iterator(
-                line.split(',').filter_map(|part| part.parse().ok()),
-            ));
+/// The beacons detected by a single scanner, relative to its own position
+/// and orientation.
+#[derive(Debug, Default, Clone)]
+pub struct Scanner {
+    points: Vec<Point>,
+    fingerprint: Fingerprint,
+    fingerprints: Vec<HashSet<Signature>>,
+}
 
-            let last = acc.last_mut().context("unable to get last")?;
-            last.push(point);
+impl Scanner {
+    /// Fingerprints every beacon the scanner detected.
+    pub fn new(points: Vec<Point>, fingerprint: Fingerprint) -> Self {
+        let distances = points
+            .iter()
+            .map(|&left| points.iter().map(|&right| distance(left, right)).collect())
+            .collect::<Vec<Vec<u128>>>();
 
-            Ok(acc)
-        })
-        .map(|scanners| {
-            scanners
+        let fingerprints = match fingerprint {
+            Fingerprint::Distances => unique_distances(&distances),
+            Fingerprint::Triangles => (0..points.len())
+                .map(|i| {
+                    (0..points.len())
+                        .filter(|&j| j != i)
+                        .tuple_combinations()
+                        .map(|(j, k)| {
+                            let (near, far) = if distances[i][j] <= distances[i][k] {
+                                (distances[i][j], distances[i][k])
+                            } else {
+                                (distances[i][k], distances[i][j])
+                            };
+                            Signature::Triangle([near, far, distances[j][k]])
+                        })
+                        .collect()
+                })
+                .collect(),
+        };
+
+        Self {
+            points,
+            fingerprint,
+            fingerprints,
+        }
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Finds the transform from this scanner's coordinates into `other`'s.
+    /// Each beacon pair with matching fingerprints suggests a translation for
+    /// every rotation, which is accepted if it lines up enough beacons.
+    fn find_transform(&self, other: &Scanner, config: &MatchConfig) -> Option<Transform> {
+        let other_points = other.points.iter().copied().collect::<HashSet<Point>>();
+        let rotations = Rotation::all().collect::<Vec<_>>();
+
+        self.overlaps(other, config)
+            .into_iter()
+            .take(config.candidates)
+            .flat_map(|(i, j)| rotations.iter().map(move |&rotation| (i, j, rotation)))
+            .map(|(i, j, rotation)| Transform {
+                rotation,
+                translation: sub(other.points[j], rotation * self.points[i]),
+            })
+            .find(|&transform| {
+                self.points
+                    .iter()
+                    .filter(|&&point| other_points.contains(&(transform * point)))
+                    .count()
+                    >= config.min_shared_beacons
+            })
+    }
+
+    /// Finds pairs of beacons whose fingerprints share enough to be the same
+    /// beacon, if the scanners share `min_shared_beacons` beacons. Each of
+    /// this scanner's beacons is compared in parallel.
+    fn overlaps(&self, other: &Scanner, config: &MatchConfig) -> Vec<(usize, usize)> {
+        // A shared beacon has a distance to every other shared beacon, and
+        // forms a triangle with every pair of them.
+        let others = config.min_shared_beacons.saturating_sub(1);
+        let min_shared = match self.fingerprint {
+            Fingerprint::Distances => others,
+            Fingerprint::Triangles => others * others.saturating_sub(1) / 2,
+        };
+
+        self.fingerprints
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(self_index, self_signatures)| {
+                other.fingerprints.iter().enumerate().filter_map(
+                    move |(other_index, other_signatures)| {
+                        let shared = self_signatures.intersection(other_signatures).count();
+                        (shared >= min_shared).then_some((self_index, other_index))
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Fingerprints each beacon by its distances to the others, dropping any
+/// distance it has to more than one beacon.
+fn unique_distances(distances: &[Vec<u128>]) -> Vec<HashSet<Signature>> {
+    distances
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let counts = row.iter().enumerate().filter(|&(j, _)| j != i).fold(
+                HashMap::<u128, usize>::new(),
+                |mut acc, (_, &distance)| {
+                    *acc.entry(distance).or_default() += 1;
+                    acc
+                },
+            );
+
+            counts
                 .into_iter()
-                .map(|points| points.into_iter().collect())
+                .filter(|&(_, count)| count == 1)
+                .map(|(distance, _)| Signature::Distance(distance))
                 .collect()
-        })?;
+        })
+        .collect()
+}
 
-    let mut transforms = HashMap::new();
-    transforms.insert(0, Transform3::identity());
+impl FromIterator<Point> for Scanner {
+    fn from_iter<T: IntoIterator<Item = Point>>(iter: T) -> Self {
+        Scanner::new(iter.into_iter().collect(), Fingerprint::default())
+    }
+}
 
-    let mut ignore = HashSet::new();
+/// Which scanners could be aligned with scanner 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Alignment {
+    /// The scanners connected to scanner 0 by overlapping scanners, in the
+    /// order they were aligned.
+    pub aligned: Vec<usize>,
+    /// The scanners with no chain of overlaps to scanner 0, in order.
+    pub unaligned: Vec<usize>,
+    /// Every pair of overlapping scanners, lowest index first.
+    pub overlaps: Vec<(usize, usize)>,
+    /// The overlaps each scanner was aligned through, as a spanning tree of
+    /// scanner 0's component. Each edge is an already aligned scanner and
+    /// the scanner aligned from it, in the order they were aligned.
+    pub tree: Vec<(usize, usize)>,
+}
 
-    let mut search = (1..scanners.len()).collect::<VecDeque<usize>>();
-    while let Some(i) = search.pop_front() {
-        let known_scanners = transforms.keys();
-        for &j in known_scanners {
-            if ignore.contains(&(i, j)) {
-                continue;
-            }
+/// The beacons detected by each scanner, in its own coordinates.
+pub type Reports = Vec<Vec<Point>>;
 
-            if let Some(transform) = scanners[i].find_transform(&scanners[j]) {
-                let other_transform = transforms[&j];
-                transforms.insert(i, other_transform * transform);
-                break;
-            } else {
-                ignore.insert((i, j));
-            }
+/// The scanners aligned once by the generator, shared by both parts.
+pub type Input = Survey;
+
+/// The scanners along with the transform from each aligned scanner's
+/// coordinates into the coordinates of scanner 0.
+pub struct Survey {
+    pub scanners: Vec<Scanner>,
+    /// The transform for each scanner, or `None` if it couldn't be aligned.
+    pub transforms: Vec<Option<Transform>>,
+    pub alignment: Alignment,
+}
+
+/// A solution over the scanners aligned with scanner 0, along with the
+/// scanners that were left out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Aligned<T> {
+    pub value: T,
+    pub left_out: Vec<usize>,
+}
+
+impl<T: fmt::Display> fmt::Display for Aligned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        if !self.left_out.is_empty() {
+            write!(
+                f,
+                " (left out unaligned scanners {})",
+                self.left_out.iter().join(", ")
+            )?;
         }
 
-        if !transforms.contains_key(&i) {
-            search.push_back(i);
+        Ok(())
+    }
+}
+
+/// A complete solution is a plain number, and a partial one is text naming
+/// the scanners left out.
+impl<T: IntoAnswer + fmt::Display> IntoAnswer for Aligned<T> {
+    fn into_answer(self) -> Answer {
+        if self.left_out.is_empty() {
+            self.value.into_answer()
+        } else {
+            Answer::Text(self.to_string())
         }
     }
+}
+
+/// Fingerprints the beacons of every scanner and tries to align every pair
+/// of scanners in parallel. Each scanner is then aligned with scanner 0
+/// through a spanning tree of the overlapping pairs, found breadth first.
+/// Fails if `config` is invalid or there are no scanners.
+pub fn align(reports: &[Vec<Point>], config: &MatchConfig) -> eyre::Result<Survey> {
+    config.validate()?;
+    if reports.is_empty() {
+        bail!("no scanners to align");
+    }
 
-    let transforms = transforms.into_iter().fold(
-        vec![Transform3::identity(); scanners.len()],
-        |mut acc, (index, transform)| {
-            acc[index] = transform;
-            acc
-        },
-    );
+    let scanners = reports
+        .par_iter()
+        .map(|points| Scanner::new(points.clone(), config.fingerprint))
+        .collect::<Vec<Scanner>>();
 
-    Ok(Input {
+    let matches = (0..scanners.len())
+        .tuple_combinations()
+        .collect::<Vec<(usize, usize)>>()
+        .into_par_iter()
+        .filter_map(|(i, j)| {
+            let transform = scanners[i].find_transform(&scanners[j], config)?;
+            Some(((i, j), transform))
+        })
+        .collect::<Vec<_>>();
+
+    // Each scanner's neighbours, with the transform from the neighbour's
+    // coordinates into the scanner's own.
+    let mut neighbours = vec![vec![]; scanners.len()];
+    for &((i, j), transform) in &matches {
+        neighbours[i].push((j, transform.inverse()));
+        neighbours[j].push((i, transform));
+    }
+
+    let mut transforms = vec![None; scanners.len()];
+    transforms[0] = Some(Transform::IDENTITY);
+    let mut aligned = vec![0];
+    let mut tree = vec![];
+    let mut search = VecDeque::from([(0, Transform::IDENTITY)]);
+    while let Some((i, transform)) = search.pop_front() {
+        for &(j, neighbour) in &neighbours[i] {
+            if transforms[j].is_none() {
+                let neighbour = transform * neighbour;
+                transforms[j] = Some(neighbour);
+                aligned.push(j);
+                tree.push((i, j));
+                search.push_back((j, neighbour));
+            }
+        }
+    }
+
+    let unaligned = (0..scanners.len())
+        .filter(|&i| transforms[i].is_none())
+        .collect();
+    let overlaps = matches.into_iter().map(|(pair, _)| pair).collect();
+    let alignment = Alignment {
+        aligned,
+        unaligned,
+        overlaps,
+        tree,
+    };
+
+    Ok(Survey {
         scanners,
         transforms,
+        alignment,
     })
 }
 
+fn beacon(line: Line) -> Result<Point, ParseError> {
+    let (x, rest) = line.split_once(",")?;
+    let (y, z) = line.split_span(rest, ",")?;
+    let coordinate = |span| line.parse::<i32>(span).map(i64::from);
+    Ok([coordinate(x)?, coordinate(y)?, coordinate(z)?])
+}
+
+/// Parses the beacons detected by each scanner and aligns them.
+#[aoc_generator(day19)]
+pub fn parse(input: &str) -> eyre::Result<Input> {
+    parse_with(input, &MatchConfig::default())
+}
+
+/// Parses the beacons detected by each scanner and aligns them, matching
+/// scanners up as `config` describes.
+pub fn parse_with(input: &str, config: &MatchConfig) -> eyre::Result<Input> {
+    align(&parse_reports(input)?, config)
+}
+
+/// Parses the beacons detected by each scanner, without aligning them.
+pub fn parse_reports(input: &str) -> Result<Reports, ParseError> {
+    let reports = lines(19, input).try_fold(vec![], |mut acc: Vec<(Line, Vec<Point>)>, line| {
+        if line.text().is_empty() {
+            return Ok(acc);
+        }
+
+        if line.text().starts_with("---") {
+            acc.push((line, vec![]));
+            return Ok(acc);
+        }
+
+        let point = beacon(line)?;
+        let (_, last) = acc
+            .last_mut()
+            .ok_or_else(|| line.error(line.text(), "beacon before scanner header"))?;
+        last.push(point);
+
+        Ok::<_, ParseError>(acc)
+    })?;
+    if reports.is_empty() {
+        return Err(empty_input(19));
+    }
+
+    Ok(reports.into_iter().map(|(_, points)| points).collect())
+}
+
+/// A beacon in scanner 0's coordinates.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MapBeacon {
+    pub position: Point,
+    /// The scanners that detected the beacon, in order.
+    pub scanners: Vec<usize>,
+}
+
+/// Where an aligned scanner is and which way it faces, in scanner 0's
+/// coordinates.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MapScanner {
+    pub scanner: usize,
+    pub position: Point,
+    /// The rotation from the scanner's coordinates into scanner 0's.
+    pub rotation: [[i64; 3]; 3],
+}
+
+/// Every beacon and scanner aligned with scanner 0, for inspecting the
+/// alignment outside of the puzzle.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Map {
+    /// The beacons, ordered by position.
+    pub beacons: Vec<MapBeacon>,
+    /// The aligned scanners, in order.
+    pub scanners: Vec<MapScanner>,
+}
+
+impl Map {
+    pub fn new(survey: &Survey) -> Self {
+        let mut beacons = BTreeMap::<Point, Vec<usize>>::new();
+        let mut scanners = vec![];
+        for (index, (scanner, transform)) in
+            survey.scanners.iter().zip(&survey.transforms).enumerate()
+        {
+            let transform = match transform {
+                Some(transform) => *transform,
+                None => continue,
+            };
+
+            for &point in &scanner.points {
+                let observers = beacons.entry(transform * point).or_default();
+                // A scanner may report the same beacon twice.
+                if observers.last() != Some(&index) {
+                    observers.push(index);
+                }
+            }
+
+            scanners.push(MapScanner {
+                scanner: index,
+                position: transform.origin(),
+                rotation: transform.rotation.matrix(),
+            });
+        }
+
+        let beacons = beacons
+            .into_iter()
+            .map(|(position, scanners)| MapBeacon { position, scanners })
+            .collect();
+
+        Self { beacons, scanners }
+    }
+
+    pub fn write_json<W: Write>(&self, mut writer: W) -> eyre::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer).wrap_err("unable to write JSON map")
+    }
+
+    /// Writes one row per beacon followed by one per scanner. Beacons list
+    /// the scanners that detected them and scanners list their rotation
+    /// matrix row by row, both separated by spaces.
+    pub fn write_csv<W: Write>(&self, writer: W) -> eyre::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(["kind", "scanner", "x", "y", "z", "scanners", "rotation"])?;
+
+        for beacon in &self.beacons {
+            let [x, y, z] = beacon.position;
+            writer.write_record([
+                "beacon".to_string(),
+                String::new(),
+                x.to_string(),
+                y.to_string(),
+                z.to_string(),
+                beacon.scanners.iter().join(" "),
+                String::new(),
+            ])?;
+        }
+
+        for scanner in &self.scanners {
+            let [x, y, z] = scanner.position;
+            writer.write_record([
+                "scanner".to_string(),
+                scanner.scanner.to_string(),
+                x.to_string(),
+                y.to_string(),
+                z.to_string(),
+                String::new(),
+                scanner.rotation.iter().flatten().join(" "),
+            ])?;
+        }
+
+        writer.flush().wrap_err("unable to write CSV map")
+    }
+
+    /// Writes an ASCII PLY point cloud, with beacons in white and scanners
+    /// in red.
+    pub fn write_ply<W: Write>(&self, mut writer: W) -> eyre::Result<()> {
+        let vertices = self
+            .beacons
+            .iter()
+            .map(|beacon| (beacon.position, [255, 255, 255]))
+            .chain(
+                self.scanners
+                    .iter()
+                    .map(|scanner| (scanner.position, [255, 0, 0])),
+            );
+
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format ascii 1.0")?;
+        writeln!(
+            writer,
+            "comment {} beacons followed by {} scanners",
+            self.beacons.len(),
+            self.scanners.len()
+        )?;
+        writeln!(
+            writer,
+            "element vertex {}",
+            self.beacons.len() + self.scanners.len()
+        )?;
+        for property in [
+            "int x",
+            "int y",
+            "int z",
+            "uchar red",
+            "uchar green",
+            "uchar blue",
+        ] {
+            writeln!(writer, "property {}", property)?;
+        }
+        writeln!(writer, "end_header")?;
+
+        for ([x, y, z], [red, green, blue]) in vertices {
+            writeln!(writer, "{} {} {} {} {} {}", x, y, z, red, green, blue)?;
+        }
+
+        writer.flush().wrap_err("unable to write PLY map")
+    }
+}
+
+impl Survey {
+    /// Wraps a solution with the scanners it left out.
+    fn aligned<T>(&self, value: T) -> Aligned<T> {
+        Aligned {
+            value,
+            left_out: self.alignment.unaligned.clone(),
+        }
+    }
+}
+
+/// Counts the distinct beacons seen by the scanners aligned with scanner 0.
 #[aoc(day19, part1)]
-fn part1(input: &Input) -> usize {
-    let beacons = input
+pub fn part1(survey: &Input) -> Aligned<usize> {
+    let beacons = survey
         .scanners
         .iter()
-        .enumerate()
-        .flat_map(|(i, scanner)| {
-            let transform = input.transforms[i];
-            scanner.points.iter().copied().map(move |local_point| {
-                let global_point = transform * local_point;
-                Vector3::from_iterator(global_point.coords.iter().map(|n| n.round() as i32)).into()
-            })
-        })
-        .collect::<HashSet<Point3<i32>>>();
+        .zip(&survey.transforms)
+        .filter_map(|(scanner, transform)| Some((scanner, (*transform)?)))
+        .flat_map(|(scanner, transform)| scanner.points.iter().map(move |&point| transform * point))
+        .collect::<HashSet<Point>>();
 
-    beacons.len()
+    survey.aligned(beacons.len())
 }
 
+/// Finds the largest Manhattan distance between any two scanners aligned with
+/// scanner 0.
 #[aoc(day19, part2)]
-fn part2(input: &Input) -> Option<i32> {
-    (0..input.scanners.len())
-        .map(|i| {
-            let origin = input.transforms[i] * point![0., 0., 0.];
-            Point3::from(Vector3::<i32>::from_iterator(
-                origin.coords.iter().map(|x| x.round() as i32),
-            ))
-        })
+pub fn part2(survey: &Input) -> eyre::Result<Aligned<u64>> {
+    let distance = survey
+        .transforms
+        .iter()
+        .flatten()
+        .map(Transform::origin)
         .tuple_combinations()
-        .map(|(left, right)| (left - right).abs().sum())
+        .map(|(left, right)| {
+            left.iter()
+                .zip(right.iter())
+                .fold(0u64, |sum, (a, b)| sum.saturating_add(a.abs_diff(*b)))
+        })
         .max()
+        .context("fewer than two scanners aligned")?;
+
+    Ok(survey.aligned(distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::{parse_override, with_overrides};
+    use eyre::ContextCompat;
+    use std::iter;
+
+    // The first two scanners of the example, which share twelve beacons.
+    const EXAMPLE: &str = "--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390";
+
+    // The rest of the example's scanners, which only line up through each
+    // other.
+    const MORE_SCANNERS: &str = "--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14";
+
+    fn full_example() -> String {
+        format!("{}\n\n{}", EXAMPLE, MORE_SCANNERS)
+    }
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let reports = parse_reports(EXAMPLE)?;
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].len(), 25);
+        assert_eq!(reports[1][0], [686, 422, 578]);
+
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.scanners.len(), 2);
+        assert_eq!(input.scanners[0].points().len(), 25);
+        assert_eq!(input.scanners[1].points()[0], [686, 422, 578]);
+        let transform = input.transforms[1].context("scanner 1 not aligned")?;
+        assert_eq!(transform.origin(), [68, -1246, -43]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotations() {
+        let rotations = Rotation::all().collect::<HashSet<_>>();
+        assert_eq!(rotations.len(), 24);
+        assert_eq!(Rotation::all().next(), Some(Rotation::IDENTITY));
+
+        for &left in &rotations {
+            for &right in &rotations {
+                assert!(rotations.contains(&(left * right)));
+            }
+        }
+
+        let facings = rotations
+            .iter()
+            .map(|&rotation| rotation * [1, 2, 3])
+            .collect::<HashSet<_>>();
+        assert_eq!(facings.len(), 24);
+    }
+
+    #[test]
+    fn test_transform() {
+        let quarter_turn = Rotation([[0, -1, 0], [1, 0, 0], [0, 0, 1]]);
+        let inner = Transform {
+            rotation: quarter_turn,
+            translation: [1, 0, 0],
+        };
+        let outer = Transform {
+            rotation: quarter_turn,
+            translation: [0, 0, 5],
+        };
+
+        let point = [2, 3, 4];
+        assert_eq!(inner * point, [-2, 2, 4]);
+        assert_eq!((outer * inner) * point, outer * (inner * point));
+        assert_eq!((outer * inner).origin(), [0, 1, 5]);
+        assert_eq!(inner.inverse() * (inner * point), point);
+        assert_eq!(inner * inner.inverse(), Transform::IDENTITY);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_matrix() {
+        let transform = Transform {
+            rotation: Rotation([[0, -1, 0], [1, 0, 0], [0, 0, 1]]),
+            translation: [1, 0, 0],
+        };
+        let matrix = nalgebra::Matrix4::from(transform);
+        let point = matrix * nalgebra::Vector4::new(2., 3., 4., 1.);
+        assert_eq!(point, nalgebra::Vector4::new(-2., 2., 4., 1.));
+    }
+
+    #[test]
+    fn test_align() -> eyre::Result<()> {
+        let mut reports = parse_reports(EXAMPLE)?;
+        reports.swap(0, 1);
+        let input = align(&reports, &MatchConfig::default())?;
+        let transform = input.transforms[1].context("scanner 1 not aligned")?;
+        assert_eq!(transform.inverse().origin(), [68, -1246, -43]);
+        assert_eq!(input.alignment.tree, [(0, 1)]);
+        assert_eq!(part1(&input).to_string(), "38");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unaligned() -> eyre::Result<()> {
+        let input = parse(&format!("{}\n\n--- scanner 2 ---\n1,2,3\n4,5,6", EXAMPLE))?;
+        assert_eq!(
+            input.alignment,
+            Alignment {
+                aligned: vec![0, 1],
+                unaligned: vec![2],
+                overlaps: vec![(0, 1)],
+                tree: vec![(0, 1)],
+            }
+        );
+        assert_eq!(input.transforms[2], None);
+
+        assert_eq!(
+            part1(&input).to_string(),
+            "38 (left out unaligned scanners 2)"
+        );
+        assert_eq!(part2(&input)?.value, 68 + 1246 + 43);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_config() -> eyre::Result<()> {
+        let config = MatchConfig {
+            fingerprint: Fingerprint::Triangles,
+            ..MatchConfig::default()
+        };
+        let reports = parse_reports(EXAMPLE)?;
+        let input = align(&reports, &config)?;
+        assert_eq!(input.alignment.overlaps, [(0, 1)]);
+        assert_eq!(part1(&input).to_string(), "38");
+
+        let config: MatchConfig = with_overrides(&[parse_override("min_shared_beacons=13")?])?;
+        let input = align(&reports, &config)?;
+        assert_eq!(input.alignment.unaligned, [1]);
+
+        assert!(with_overrides::<MatchConfig>(&[parse_override("fingerprint=cubes")?]).is_err());
+
+        for (config, message) in [
+            (
+                "min_shared_beacons=0",
+                "min_shared_beacons must be at least 2, found 0",
+            ),
+            (
+                "min_shared_beacons=1",
+                "min_shared_beacons must be at least 2, found 1",
+            ),
+            ("candidates=0", "candidates must be at least 1"),
+        ] {
+            let config: MatchConfig = with_overrides(&[parse_override(config)?])?;
+            let error = align(&reports, &config)
+                .err()
+                .context("invalid config accepted")?;
+            assert_eq!(error.to_string(), message);
+            assert!(parse_with(EXAMPLE, &config).is_err());
+        }
+
+        let config = MatchConfig {
+            candidates: 1,
+            ..MatchConfig::default()
+        };
+        assert_eq!(part2(&parse_with(EXAMPLE, &config)?)?.value, 68 + 1246 + 43);
+        assert!(part2(&align(&reports[..1], &config)?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_scanners() -> eyre::Result<()> {
+        let error = align(&[], &MatchConfig::default())
+            .err()
+            .context("empty reports aligned")?;
+        assert_eq!(error.to_string(), "no scanners to align");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse() -> eyre::Result<()> {
+        let first = parse(EXAMPLE)?.scanners.swap_remove(0);
+        let transform = Transform {
+            rotation: Rotation([[0, 0, 1], [1, 0, 0], [0, 1, 0]]),
+            translation: [100, -20, 7],
+        };
+        let mut points = first.points()[..6]
+            .iter()
+            .map(|&point| transform * point)
+            .collect::<Vec<_>>();
+        points.extend([[5, 5, 5], [-300, 12, 900]]);
+
+        let report = iter::once("--- scanner 0 ---".to_string())
+            .chain(first.points().iter().map(|point| point.iter().join(",")))
+            .chain(iter::once("\n--- scanner 1 ---".to_string()))
+            .chain(points.iter().map(|point| point.iter().join(",")))
+            .join("\n");
+
+        let reports = parse_reports(&report)?;
+        assert_eq!(
+            align(&reports, &MatchConfig::default())?
+                .alignment
+                .unaligned,
+            [1]
+        );
+
+        for fingerprint in [Fingerprint::Distances, Fingerprint::Triangles] {
+            let config = MatchConfig {
+                min_shared_beacons: 6,
+                fingerprint,
+                candidates: 4,
+            };
+            let input = align(&reports, &config)?;
+            assert_eq!(input.transforms[1], Some(transform.inverse()));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let map = Map::new(&input);
+        assert_eq!(map.beacons.len(), 38);
+        let shared = map
+            .beacons
+            .iter()
+            .filter(|beacon| beacon.scanners == [0, 1])
+            .count();
+        assert_eq!(shared, 12);
+
+        let transform = input.transforms[1].context("scanner 1 not aligned")?;
+        assert_eq!(
+            map.scanners[1],
+            MapScanner {
+                scanner: 1,
+                position: [68, -1246, -43],
+                rotation: transform.rotation.matrix(),
+            }
+        );
+
+        let mut json = vec![];
+        map.write_json(&mut json)?;
+        let json: serde_json::Value = serde_json::from_slice(&json)?;
+        assert_eq!(
+            json["scanners"][0],
+            serde_json::json!({
+                "scanner": 0,
+                "position": [0, 0, 0],
+                "rotation": [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            })
+        );
+        assert_eq!(json["beacons"].as_array().map(Vec::len), Some(38));
+
+        let mut csv = vec![];
+        map.write_csv(&mut csv)?;
+        let csv = String::from_utf8(csv)?;
+        let mut rows = csv.lines();
+        assert_eq!(rows.next(), Some("kind,scanner,x,y,z,scanners,rotation"));
+        assert_eq!(rows.next(), Some("beacon,,-892,524,684,0,"));
+        assert_eq!(
+            rows.last(),
+            Some(
+                format!(
+                    "scanner,1,68,-1246,-43,,{}",
+                    transform.rotation.matrix().iter().flatten().join(" ")
+                )
+                .as_str()
+            )
+        );
+
+        let mut ply = vec![];
+        map.write_ply(&mut ply)?;
+        let ply = String::from_utf8(ply)?;
+        let (header, body) = ply.split_once("end_header\n").context("missing header")?;
+        assert!(header.starts_with("ply\nformat ascii 1.0\n"));
+        assert!(header.contains("element vertex 40\n"));
+        assert_eq!(body.lines().count(), 40);
+        assert_eq!(body.lines().last(), Some("68 -1246 -43 255 0 0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input).to_string(), "38");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let answer = part2(&input)?;
+        assert_eq!(answer.value, 68 + 1246 + 43);
+        assert!(answer.left_out.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_full_example() -> eyre::Result<()> {
+        let input = parse(&full_example())?;
+        let origins = input
+            .transforms
+            .iter()
+            .map(|transform| transform.map(|transform| transform.origin()))
+            .collect::<Vec<_>>();
+        let expected = [
+            [0, 0, 0],
+            [68, -1246, -43],
+            [1105, -1205, 1229],
+            [-92, -2380, -20],
+            [-20, -1133, 1061],
+        ];
+        assert_eq!(origins, expected.map(Some));
+
+        assert_eq!(part1(&input).to_string(), "79");
+        let answer = part2(&input)?;
+        assert_eq!(answer.value, 3621);
+        assert!(answer.left_out.is_empty());
+
+        Ok(())
+    }
 }
@@ -1,35 +1,42 @@
-use eyre::{bail, Context, ContextCompat};
+//! Day 2: Dive!
+
+use crate::error::{lines, Line, ParseError};
+use eyre::ContextCompat;
 use std::str::FromStr;
 
-#[derive(Copy, Clone)]
-enum Command {
+/// A single submarine instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
     Forward(u32),
     Down(u32),
     Up(u32),
 }
 
+fn command(line: Line) -> Result<Command, ParseError> {
+    let (tag, amount) = line.split_once(" ")?;
+    let amount = line.parse(amount)?;
+
+    let command = match tag {
+        "forward" => Command::Forward(amount),
+        "down" => Command::Down(amount),
+        "up" => Command::Up(amount),
+        _ => return Err(line.error(tag, "unknown command")),
+    };
+
+    Ok(command)
+}
+
 impl FromStr for Command {
-    type Err = eyre::Report;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
-        let tag = parts.next().context("no command")?;
-        let amount: u32 = parts
-            .next()
-            .context("no amount")
-            .and_then(|value| value.parse().context("unable to parse amount"))?;
-
-        let command = match tag {
-            "forward" => Command::Forward(amount),
-            "down" => Command::Down(amount),
-            "up" => Command::Up(amount),
-            _ => bail!("unable to construct command"),
-        };
-
-        Ok(command)
+        command(Line::new(2, 1, s))
     }
 }
 
+/// The planned course, one command per line.
+pub type Input = Vec<Command>;
+
 #[derive(Default)]
 struct State {
     position: u32,
@@ -37,42 +44,117 @@ struct State {
     aim: u32,
 }
 
+/// Parses one `forward`, `down` or `up` command per line.
 #[aoc_generator(day2)]
-fn generator(input: &str) -> eyre::Result<Vec<Command>> {
-    input.lines().map(str::parse).collect()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    lines(2, input).map(command).collect()
 }
 
+/// Multiplies the final position and depth, treating `down` and `up` as depth changes.
 #[aoc(day2, part1)]
-fn part1(input: &[Command]) -> u32 {
+pub fn part1(input: &[Command]) -> eyre::Result<u32> {
     let State {
         position, depth, ..
-    } = input.iter().fold(State::default(), |mut state, &command| {
-        match command {
-            Command::Forward(amount) => state.position += amount,
-            Command::Down(amount) => state.depth += amount,
-            Command::Up(amount) => state.depth -= amount,
-        }
-        state
-    });
-
-    position * depth
+    } = input
+        .iter()
+        .try_fold(State::default(), |mut state, &command| {
+            match command {
+                Command::Forward(amount) => {
+                    state.position = state
+                        .position
+                        .checked_add(amount)
+                        .context("position overflowed")?
+                }
+                Command::Down(amount) => {
+                    state.depth = state
+                        .depth
+                        .checked_add(amount)
+                        .context("depth overflowed")?
+                }
+                Command::Up(amount) => {
+                    state.depth = state
+                        .depth
+                        .checked_sub(amount)
+                        .context("rose above the surface")?
+                }
+            }
+            Ok::<_, eyre::Report>(state)
+        })?;
+
+    position.checked_mul(depth).context("answer overflowed")
 }
 
+/// Multiplies the final position and depth, treating `down` and `up` as aim changes.
 #[aoc(day2, part2)]
-fn part2(input: &[Command]) -> u32 {
+pub fn part2(input: &[Command]) -> eyre::Result<u32> {
     let State {
         position, depth, ..
-    } = input.iter().fold(State::default(), |mut state, &command| {
-        match command {
-            Command::Forward(amount) => {
-                state.position += amount;
-                state.depth += state.aim * amount;
+    } = input
+        .iter()
+        .try_fold(State::default(), |mut state, &command| {
+            match command {
+                Command::Forward(amount) => {
+                    state.position = state
+                        .position
+                        .checked_add(amount)
+                        .context("position overflowed")?;
+                    state.depth = state
+                        .aim
+                        .checked_mul(amount)
+                        .and_then(|change| state.depth.checked_add(change))
+                        .context("depth overflowed")?;
+                }
+                Command::Down(amount) => {
+                    state.aim = state.aim.checked_add(amount).context("aim overflowed")?
+                }
+                Command::Up(amount) => {
+                    state.aim = state
+                        .aim
+                        .checked_sub(amount)
+                        .context("aimed above the surface")?
+                }
             }
-            Command::Down(amount) => state.aim += amount,
-            Command::Up(amount) => state.aim -= amount,
-        }
-        state
-    });
+            Ok::<_, eyre::Report>(state)
+        })?;
+
+    position.checked_mul(depth).context("answer overflowed")
+}
 
-    position * depth
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.len(), 6);
+        assert_eq!(input[0], Command::Forward(5));
+        assert_eq!(input[1], Command::Down(5));
+        assert_eq!(input[3], Command::Up(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input)?, 150);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input)?, 900);
+
+        Ok(())
+    }
 }
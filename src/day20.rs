@@ -1,56 +1,51 @@
-use eyre::ContextCompat;
-use itertools::{iproduct, Itertools};
-use std::{collections::HashMap, iter};
+//! Day 20: Trench Map
 
-type Point = (i16, i16);
+use crate::{
+    error::{empty_input, lines, ParseError},
+    grid::Grid,
+    params::define_params,
+};
+use std::iter;
 
+/// A window onto an infinite image. Every pixel outside of the window has
+/// the value of `space_pixel`.
 #[derive(Clone, Debug)]
-struct Image {
-    pixels: HashMap<Point, bool>,
-    space_pixel: bool,
+pub struct Image {
+    pub pixels: Grid<bool>,
+    pub space_pixel: bool,
 }
 
-fn enhance(image: Image, enhancement: &[bool]) -> impl Iterator<Item = Image> + '_ {
+const WINDOW: [(isize, isize); 9] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (0, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Yields the image after each round of enhancement.
+pub fn enhance(image: Image, enhancement: &[bool]) -> impl Iterator<Item = Image> + '_ {
     iter::successors(Some(image), |previous| {
-        let (&(startx, _), &(endx, _)) = previous
-            .pixels
-            .keys()
-            .minmax_by_key(|(x, _)| x)
-            .into_option()?;
-        let (&(_, starty), &(_, endy)) = previous
-            .pixels
-            .keys()
-            .minmax_by_key(|(_, y)| y)
-            .into_option()?;
-
-        let pixels = iproduct!(((startx - 1)..=(endx + 1)), ((starty - 1)..=(endy + 1)))
-            .map(|(x, y)| {
-                let lookup = [
-                    (-1, -1),
-                    (0, -1),
-                    (1, -1),
-                    (-1, 0),
-                    (0, 0),
-                    (1, 0),
-                    (-1, 1),
-                    (0, 1),
-                    (1, 1),
-                ]
-                .into_iter()
-                .map(move |(dx, dy)| (x + dx, y + dy))
-                .fold(0, |acc, point| {
-                    let bit = previous
-                        .pixels
-                        .get(&point)
-                        .copied()
-                        .unwrap_or(previous.space_pixel) as usize;
-                    (acc << 1) + bit
-                });
-
-                let value = enhancement[lookup];
-                ((x, y), value)
-            })
-            .collect();
+        let width = previous.pixels.width() + 2;
+        let height = previous.pixels.height() + 2;
+
+        let pixels = Grid::from_fn(width, height, |(x, y)| {
+            let lookup = WINDOW.into_iter().fold(0, |acc, (dx, dy)| {
+                let bit = x
+                    .checked_add_signed(dx - 1)
+                    .zip(y.checked_add_signed(dy - 1))
+                    .and_then(|point| previous.pixels.get(point))
+                    .copied()
+                    .unwrap_or(previous.space_pixel) as usize;
+                (acc << 1) + bit
+            });
+
+            enhancement.get(lookup).copied().unwrap_or_default()
+        });
 
         let space_pixel = if previous.space_pixel {
             enhancement.last().copied().unwrap_or_default()
@@ -68,38 +63,44 @@ fn enhance(image: Image, enhancement: &[bool]) -> impl Iterator<Item = Image> +
     .skip(1)
 }
 
+/// The image enhancement algorithm and the input image.
 #[derive(Debug)]
-struct Input {
-    enhancement: Vec<bool>,
-    image: Image,
+pub struct Input {
+    pub enhancement: Vec<bool>,
+    pub image: Image,
 }
 
-fn parse_pixel(pixel: char) -> bool {
+fn parse_pixel(pixel: char) -> Option<bool> {
     match pixel {
-        '#' => true,
-        '.' => false,
-        c => panic!("unrecognizable value {}", c),
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
     }
 }
 
+/// Parses the enhancement algorithm, a blank line and the image.
 #[aoc_generator(day20)]
-fn generator(input: &str) -> eyre::Result<Input> {
-    let mut lines = input.lines();
-    let enhancement = lines
-        .next()
-        .map(|line| line.chars().map(parse_pixel).collect())
-        .context("unable to read enhancement")?;
-
-    let pixels = lines
-        .skip(1)
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars().enumerate().map(move |(x, c)| {
-                let value = parse_pixel(c);
-                ((x as i16, y as i16), value)
-            })
-        })
-        .collect();
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let mut lines = lines(20, input);
+    let first = lines.next().ok_or_else(|| empty_input(20))?;
+    let text = first.check_chars(
+        first.text(),
+        |c| parse_pixel(c).is_some(),
+        "unexpected character",
+    )?;
+    let enhancement = text.chars().filter_map(parse_pixel).collect::<Vec<bool>>();
+    if enhancement.len() != 512 {
+        let message = format!("expected 512 pixels, found {}", enhancement.len());
+        return Err(first.error(text, message));
+    }
+
+    match lines.next() {
+        Some(blank) if blank.text().is_empty() => {}
+        Some(line) => return Err(line.error(line.text(), "expected a blank line")),
+        None => return Err(first.end_of_input("expected an image")),
+    }
+
+    let pixels = Grid::parse_chars(lines, parse_pixel)?;
     let image = Image {
         pixels,
         space_pixel: false,
@@ -108,30 +109,115 @@ fn generator(input: &str) -> eyre::Result<Input> {
     Ok(Input { enhancement, image })
 }
 
-#[aoc(day20, part1)]
-fn part1(input: &Input) -> Option<usize> {
+define_params! {
+    /// How many times the image is enhanced.
+    pub struct Params {
+        /// Enhancements applied by part 1.
+        pub part1_rounds: usize = 2,
+        /// Enhancements applied by part 2.
+        pub part2_rounds: usize = 50,
+    }
+}
+
+/// Counts the lit pixels after `rounds` enhancements, or `None` if
+/// infinitely many are lit.
+pub fn solve(input: &Input, rounds: usize) -> Option<usize> {
     let image = input.image.clone();
-    let count = enhance(image, &input.enhancement)
-        .take(2)
-        .last()?
-        .pixels
-        .values()
-        .filter(|&v| *v)
-        .count();
-
-    Some(count)
+    let image = iter::once(image.clone())
+        .chain(enhance(image, &input.enhancement))
+        .nth(rounds)?;
+    if image.space_pixel {
+        return None;
+    }
+
+    Some(image.pixels.values().filter(|&v| *v).count())
 }
 
+/// Counts the lit pixels after two enhancements.
+#[aoc(day20, part1)]
+pub fn part1(input: &Input) -> Option<usize> {
+    part1_with(input, &Params::default())
+}
+
+/// Counts the lit pixels after `part1_rounds` enhancements.
+pub fn part1_with(input: &Input, params: &Params) -> Option<usize> {
+    solve(input, params.part1_rounds)
+}
+
+/// Counts the lit pixels after fifty enhancements.
 #[aoc(day20, part2)]
-fn part2(input: &Input) -> Option<usize> {
-    let image = input.image.clone();
-    let count = enhance(image, &input.enhancement)
-        .take(50)
-        .last()?
-        .pixels
-        .values()
-        .filter(|&v| *v)
-        .count();
-
-    Some(count)
+pub fn part2(input: &Input) -> Option<usize> {
+    part2_with(input, &Params::default())
+}
+
+/// Counts the lit pixels after `part2_rounds` enhancements.
+pub fn part2_with(input: &Input, params: &Params) -> Option<usize> {
+    solve(input, params.part2_rounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#..#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#......#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#.....####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.......##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
+
+#..#.
+#....
+##..#
+..#..
+..###";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.enhancement.len(), 512);
+        assert!(input.enhancement[34]);
+        assert_eq!(input.image.pixels.width(), 5);
+        assert_eq!(input.image.pixels.height(), 5);
+        assert_eq!(input.image.pixels.values().filter(|&&lit| lit).count(), 10);
+        assert!(!input.image.space_pixel);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_enhance() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let mut images = enhance(input.image.clone(), &input.enhancement);
+
+        let image = images.next().expect("no enhanced image");
+        assert_eq!(image.pixels.width(), 7);
+        assert_eq!(image.pixels.values().filter(|&&lit| lit).count(), 24);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), Some(35));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), Some(3351));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_params() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let params = Params {
+            part1_rounds: 0,
+            part2_rounds: 1,
+        };
+        assert_eq!(part1_with(&input, &params), Some(10));
+        assert_eq!(part2_with(&input, &params), Some(24));
+
+        Ok(())
+    }
 }
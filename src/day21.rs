@@ -1,113 +1,228 @@
-use eyre::ContextCompat;
-use itertools::Itertools;
+//! Day 21: Dirac Dice
+
+use crate::{
+    error::{empty_input, lines, ParseError},
+    params::define_params,
+};
+use eyre::{bail, ContextCompat};
 use std::collections::HashMap;
 
+/// The starting position of each player.
+pub type Input = Vec<u8>;
+
+/// Parses the starting position of each player.
 #[aoc_generator(day21)]
-fn generator(input: &str) -> eyre::Result<Vec<u8>> {
-    input
-        .lines()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let mut last = None;
+    let positions = lines(21, input)
         .map(|line| {
-            line.chars()
-                .last()
-                .and_then(|c| c.to_digit(10).map(|d| d as u8))
-                .context("can't read start position")
+            last = Some(line);
+            if line.number() > 2 {
+                return Err(line.error(line.text(), "expected only two players"));
+            }
+
+            let (_, position) = line.split_once(": ")?;
+            match line.parse(position)? {
+                0 => Err(line.error(position, "position must be at least 1")),
+                position => Ok(position),
+            }
         })
-        .collect()
+        .collect::<Result<Input, _>>()?;
+
+    match last {
+        Some(line) if positions.len() < 2 => Err(line.end_of_input("expected two players")),
+        None => Err(empty_input(21)),
+        _ => Ok(positions),
+    }
 }
 
-struct GameState {
-    position: Vec<u8>,
-    scores: Vec<usize>,
-    rolls: usize,
-    previous_score: usize,
+define_params! {
+    /// The board and dice the game is played with.
+    pub struct Params {
+        /// The number of spaces on the circular board.
+        pub board_size: u8 = 10,
+        /// The score needed to win with the deterministic die.
+        pub deterministic_target: usize = 1000,
+        /// The number of sides on the deterministic die.
+        pub die_sides: usize = 100,
+        /// The score needed to win with the Dirac die.
+        pub dirac_target: usize = 21,
+    }
 }
 
-#[aoc(day21, part1)]
-fn part1(input: &[u8]) -> Option<usize> {
-    let state = GameState {
-        position: input.to_vec(),
-        scores: vec![0, 0],
-        rolls: 0,
-        previous_score: 0,
+/// Checks that there are two players who both start on the board.
+fn starting_positions(input: &[u8], params: &Params) -> eyre::Result<[usize; 2]> {
+    let positions = match *input {
+        [first, second] => [first, second],
+        _ => bail!("expected two players, found {}", input.len()),
     };
 
-    [0, 1]
-        .into_iter()
-        .cycle()
-        .zip((0..).chunks(3).into_iter())
-        .scan(state, |state, (player, moves)| {
-            for round in moves {
-                let roll = ((round % 100) + 1) as u8;
-                state.position[player] = 1 + (state.position[player] + roll - 1) % 10;
-                state.rolls += 1;
-            }
+    for position in positions {
+        if !(1..=params.board_size).contains(&position) {
+            bail!(
+                "position {} is not between 1 and {}",
+                position,
+                params.board_size
+            );
+        }
+    }
 
-            let other = (player + 1) % 2;
-            state.scores[player] += state.position[player] as usize;
-            state.previous_score = state.scores[other];
+    Ok(positions.map(usize::from))
+}
 
-            let player_score = state.scores[player];
-            Some((state.previous_score, player_score, state.rolls))
-        })
-        .take_while(|&(score, _, _)| score < 1000)
-        .last()
-        .map(|(score, _, rolls)| score * rolls)
+/// Plays with the deterministic die, multiplying the losing score by the
+/// number of rolls.
+#[aoc(day21, part1)]
+pub fn part1(input: &[u8]) -> eyre::Result<usize> {
+    part1_with(input, &Params::default())
+}
+
+/// Plays to `deterministic_target` with a `die_sides` sided deterministic
+/// die.
+pub fn part1_with(input: &[u8], params: &Params) -> eyre::Result<usize> {
+    let mut positions = starting_positions(input, params)?;
+    if params.die_sides == 0 {
+        bail!("the die needs at least one side");
+    }
+
+    let board_size = usize::from(params.board_size);
+    let mut scores = [0usize; 2];
+    let mut rolls = 0usize;
+    let mut player = 0;
+    loop {
+        for _ in 0..3 {
+            let roll = rolls % params.die_sides + 1;
+            positions[player] = 1 + (positions[player] - 1 + roll % board_size) % board_size;
+            rolls += 1;
+        }
+
+        scores[player] = scores[player]
+            .checked_add(positions[player])
+            .context("score overflowed")?;
+        if scores[player] >= params.deterministic_target {
+            return scores[1 - player]
+                .checked_mul(rolls)
+                .context("result overflowed");
+        }
+
+        player = 1 - player;
+    }
 }
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 struct PlayerState {
-    score: u8,
-    position: u8,
+    score: usize,
+    position: usize,
 }
 
-type Cache = HashMap<(PlayerState, PlayerState), (usize, usize)>;
+const FREQUENCIES: [(usize, usize); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
+
+/// Counts the universes each player wins in, or `None` if the counts
+/// overflow. Universes are played forward a turn at a time, counting how many
+/// share each pair of player states.
+fn solve_quantum(players: (PlayerState, PlayerState), params: &Params) -> Option<[usize; 2]> {
+    let board_size = usize::from(params.board_size);
+    let mut wins = [0usize; 2];
+    let mut universes = HashMap::from([(players, 1usize)]);
+    let mut player = 0;
+    while !universes.is_empty() {
+        let mut next = HashMap::new();
+        for ((active, other), count) in universes {
+            if active.score >= params.dirac_target {
+                wins[player] = wins[player].checked_add(count)?;
+                continue;
+            }
 
-const FREQUENCIES: [(u8, usize); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
+            for (roll, frequency) in FREQUENCIES {
+                let position = 1 + (active.position - 1 + roll) % board_size;
+                let score = active.score + position;
+                let count = count.checked_mul(frequency)?;
+                if score >= params.dirac_target {
+                    wins[player] = wins[player].checked_add(count)?;
+                } else {
+                    let universe = next.entry((other, PlayerState { score, position }));
+                    let total: &mut usize = universe.or_default();
+                    *total = total.checked_add(count)?;
+                }
+            }
+        }
 
-fn solve_quantum(
-    players @ (active, other): (PlayerState, PlayerState),
-    cache: &mut Cache,
-) -> (usize, usize) {
-    if let Some(&scores) = cache.get(&players) {
-        return scores;
+        universes = next;
+        player = 1 - player;
     }
 
-    if active.score >= 21 {
-        return (1, 0);
-    } else if other.score >= 21 {
-        return (0, 1);
-    }
+    Some(wins)
+}
+
+/// Plays with the Dirac die, counting the universes the best player wins in.
+#[aoc(day21, part2)]
+pub fn part2(input: &[u8]) -> eyre::Result<usize> {
+    part2_with(input, &Params::default())
+}
+
+/// Plays to `dirac_target` with the Dirac die.
+pub fn part2_with(input: &[u8], params: &Params) -> eyre::Result<usize> {
+    let [player1, player2] =
+        starting_positions(input, params)?.map(|position| PlayerState { score: 0, position });
+
+    let [player1_wins, player2_wins] =
+        solve_quantum((player1, player2), params).context("universe counts overflowed")?;
+
+    Ok(player1_wins.max(player2_wins))
+}
 
-    let mut scores = [0; 2];
-    for (roll, frequency) in FREQUENCIES {
-        let position = 1 + (active.position + roll - 1) % 10;
-        let score = active.score + position;
-        let next = PlayerState { score, position };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let (other_wins, active_wins) = solve_quantum((other, next), cache);
+    const EXAMPLE: &str = "Player 1 starting position: 4
+Player 2 starting position: 8";
 
-        scores[0] += active_wins * frequency;
-        scores[1] += other_wins * frequency;
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        assert_eq!(parse(EXAMPLE)?, [4, 8]);
+
+        Ok(())
     }
 
-    let score_tuple = (scores[0], scores[1]);
-    cache.insert(players, score_tuple);
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input)?, 739785);
 
-    score_tuple
-}
+        Ok(())
+    }
 
-#[aoc(day21, part2)]
-fn part2(input: &[u8]) -> usize {
-    let mut cache = HashMap::new();
-    let player1 = PlayerState {
-        score: 0,
-        position: input[0] as u8,
-    };
-    let player2 = PlayerState {
-        score: 0,
-        position: input[1] as u8,
-    };
-    let (player1_wins, player2_wins) = solve_quantum((player1, player2), &mut cache);
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input)?, 444356092776315);
+
+        Ok(())
+    }
 
-    player1_wins.max(player2_wins)
+    #[test]
+    fn test_params() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let params = Params {
+            board_size: 8,
+            deterministic_target: 10,
+            die_sides: 6,
+            dirac_target: 3,
+        };
+        assert_eq!(part1_with(&input, &params)?, 63);
+        assert_eq!(part2_with(&input, &params)?, 1805);
+        assert!(part1_with(&[4, 9], &params).is_err());
+
+        let params = Params {
+            dirac_target: 1_000_000,
+            ..Params::default()
+        };
+        let error = part2_with(&input, &params)
+            .err()
+            .context("expected an error")?;
+        assert_eq!(error.to_string(), "universe counts overflowed");
+
+        Ok(())
+    }
 }
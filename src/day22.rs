@@ -1,68 +1,88 @@
-use eyre::ContextCompat;
-use itertools::iproduct;
+//! Day 22: Reactor Reboot
+
+use crate::{
+    error::{lines, Line, ParseError},
+    params::define_params,
+};
 use regex::Regex;
-use std::collections::HashSet;
 
-type Point = (i64, i64, i64);
-type Input = Vec<(bool, (Point, Point))>;
+pub type Point = (i64, i64, i64);
+/// The reboot steps as the state to set and the inclusive cuboid to set it
+/// on.
+pub type Input = Vec<(bool, (Point, Point))>;
+
+fn range(line: &Line, start: &str, end: &str) -> Result<(i64, i64), ParseError> {
+    let first = line.parse::<i32>(start)?;
+    let last = line.parse::<i32>(end)?;
+    if first > last {
+        return Err(line.error(start, "range starts after it ends"));
+    }
+
+    Ok((first.into(), last.into()))
+}
 
+/// Parses one `on|off x=..,y=..,z=..` reboot step per line.
 #[aoc_generator(day22)]
-fn generator(input: &str) -> eyre::Result<Input> {
+pub fn parse(input: &str) -> Result<Input, ParseError> {
     let re = Regex::new(
-        r"(?P<state>on|off) x=(?P<x1>-?\d+)..(?P<x2>-?\d+),y=(?P<y1>-?\d+)..(?P<y2>-?\d+),z=(?P<z1>-?\d+)..(?P<z2>-?\d+)",
-    )?;
+        r"^(?P<state>on|off) x=(?P<x1>-?\d+)\.\.(?P<x2>-?\d+),y=(?P<y1>-?\d+)\.\.(?P<y2>-?\d+),z=(?P<z1>-?\d+)\.\.(?P<z2>-?\d+)$",
+    )
+    .expect("invalid step pattern");
 
-    input
-        .lines()
+    lines(22, input)
         .map(|line| {
-            let captures = re.captures(line)?;
+            let captures = re
+                .captures(line.text())
+                .ok_or_else(|| line.error(line.text(), "expected a reboot step"))?;
             let state = &captures["state"] == "on";
-            let x1 = captures["x1"].parse().ok()?;
-            let x2 = captures["x2"].parse().ok()?;
-            let y1 = captures["y1"].parse().ok()?;
-            let y2 = captures["y2"].parse().ok()?;
-            let z1 = captures["z1"].parse().ok()?;
-            let z2 = captures["z2"].parse().ok()?;
-            Some((state, ((x1, y1, z1), (x2, y2, z2))))
+            let (x1, x2) = range(&line, &captures["x1"], &captures["x2"])?;
+            let (y1, y2) = range(&line, &captures["y1"], &captures["y2"])?;
+            let (z1, z2) = range(&line, &captures["z1"], &captures["z2"])?;
+            Ok((state, ((x1, y1, z1), (x2, y2, z2))))
         })
-        .collect::<Option<Input>>()
-        .context("unable to parse input")
+        .collect()
 }
 
+define_params! {
+    /// The bounds of the initialization procedure.
+    pub struct Params {
+        /// How far the initialization region extends from the origin along
+        /// each axis.
+        pub region: u32 = 50,
+    }
+}
+
+/// Counts the cubes left on within the initialization region.
 #[aoc(day22, part1)]
-fn part1(input: &Input) -> usize {
-    let cubes = input
-        .iter()
-        .filter(|&&(_, ((x1, y1, z1), (x2, y2, z2)))| {
-            x1 >= -50 && x2 <= 50 && y1 >= -50 && y2 <= 50 && z1 >= -50 && z2 <= 50
-        })
-        .fold(
-            HashSet::new(),
-            |mut acc, &(state, ((x1, y1, z1), (x2, y2, z2)))| {
-                for point in iproduct!(x1..=x2, y1..=y2, z1..=z2) {
-                    if state {
-                        acc.insert(point);
-                    } else {
-                        acc.remove(&point);
-                    }
-                }
-
-                acc
-            },
-        );
-
-    cubes.len()
+pub fn part1(input: &Input) -> u128 {
+    part1_with(input, &Params::default())
 }
 
+/// Counts the cubes left on after the steps that lie within `region` of the
+/// origin.
+pub fn part1_with(input: &Input, params: &Params) -> u128 {
+    let region = i64::from(params.region);
+    let bounds = -region..=region;
+    let inside =
+        |(x, y, z): Point| bounds.contains(&x) && bounds.contains(&y) && bounds.contains(&z);
+
+    reboot(
+        input
+            .iter()
+            .filter(|&&(_, (min, max))| inside(min) && inside(max)),
+    )
+}
+
+/// A cuboid with the cuboids that have been removed from it.
 #[derive(Debug, PartialEq)]
-struct Cube {
+pub struct Cube {
     min: Point,
     max: Point,
     subtracted: Vec<Cube>,
 }
 
 impl Cube {
-    fn new(min: Point, max: Point) -> Self {
+    pub fn new(min: Point, max: Point) -> Self {
         Self {
             min,
             max,
@@ -70,17 +90,21 @@ impl Cube {
         }
     }
 
-    fn volume(&self) -> usize {
+    /// Counts the cubes that remain after the subtractions. Each side is at
+    /// most 2^32 long, so the volume always fits.
+    pub fn volume(&self) -> u128 {
         let (x1, y1, z1) = self.min;
         let (x2, y2, z2) = self.max;
 
-        let volume = ((x2 - x1 + 1) * (y2 - y1 + 1) * (z2 - z1 + 1)) as usize;
-        let subtracted_volume = self.subtracted.iter().map(Cube::volume).sum::<usize>();
+        let side = |min: i64, max: i64| max.abs_diff(min) as u128 + 1;
+        let volume = side(x1, x2) * side(y1, y2) * side(z1, z2);
+        let subtracted_volume = self.subtracted.iter().map(Cube::volume).sum::<u128>();
 
         volume - subtracted_volume
     }
 
-    fn subtract(&mut self, other: &Cube) {
+    /// Removes `other` from this cuboid.
+    pub fn subtract(&mut self, other: &Cube) {
         if let Some(intersection) = self.intersection(other) {
             for sub in self.subtracted.iter_mut() {
                 sub.subtract(&intersection);
@@ -90,7 +114,7 @@ impl Cube {
         }
     }
 
-    fn intersection(&self, other: &Cube) -> Option<Cube> {
+    pub fn intersection(&self, other: &Cube) -> Option<Cube> {
         let (x1, y1, z1) = self.min;
         let (x2, y2, z2) = self.max;
         let (ox1, oy1, oz1) = other.min;
@@ -111,10 +135,9 @@ impl Cube {
     }
 }
 
-#[aoc(day22, part2)]
-fn part2(input: &Input) -> usize {
-    input
-        .iter()
+/// Counts the cubes left on after applying `steps` in order.
+fn reboot<'a>(steps: impl Iterator<Item = &'a (bool, (Point, Point))>) -> u128 {
+    steps
         .map(|&(state, (min, max))| (state, Cube::new(min, max)))
         .fold(Vec::<Cube>::new(), |mut acc, (state, cube)| {
             for existing in acc.iter_mut() {
@@ -131,3 +154,135 @@ fn part2(input: &Input) -> usize {
         .map(Cube::volume)
         .sum()
 }
+
+/// Counts the cubes left on after every reboot step.
+#[aoc(day22, part2)]
+pub fn part2(input: &Input) -> u128 {
+    reboot(input.iter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::{parse_override, with_overrides};
+
+    const EXAMPLE: &str = "on x=10..12,y=10..12,z=10..12
+on x=11..13,y=11..13,z=11..13
+off x=9..11,y=9..11,z=9..11
+on x=10..10,y=10..10,z=10..10";
+
+    // The larger example from part two, most of which lies outside the
+    // initialization region.
+    const REBOOT: &str = "on x=-5..47,y=-31..22,z=-19..33
+on x=-44..5,y=-27..21,z=-14..35
+on x=-49..-1,y=-11..42,z=-10..38
+on x=-20..34,y=-40..6,z=-44..1
+off x=26..39,y=40..50,z=-2..11
+on x=-41..5,y=-41..6,z=-36..8
+off x=-43..-33,y=-45..-28,z=7..25
+on x=-33..15,y=-32..19,z=-34..11
+off x=35..47,y=-46..-34,z=-11..5
+on x=-14..36,y=-6..44,z=-16..29
+on x=-57795..-6158,y=29564..72030,z=20435..90618
+on x=36731..105352,y=-21140..28532,z=16094..90401
+on x=30999..107136,y=-53464..15513,z=8553..71215
+on x=13528..83982,y=-99403..-27377,z=-24141..23996
+on x=-72682..-12347,y=18159..111354,z=7391..80950
+on x=-1060..80757,y=-65301..-20884,z=-103788..-16709
+on x=-83015..-9461,y=-72160..-8347,z=-81239..-26856
+on x=-52752..22273,y=-49450..9096,z=54442..119054
+on x=-29982..40483,y=-108474..-28371,z=-24328..38471
+on x=-4958..62750,y=40422..118853,z=-7672..65583
+on x=55694..108686,y=-43367..46958,z=-26781..48729
+on x=-98497..-18186,y=-63569..3412,z=1232..88485
+on x=-726..56291,y=-62629..13224,z=18033..85226
+on x=-110886..-34664,y=-81338..-8658,z=8914..63723
+on x=-55829..24974,y=-16897..54165,z=-121762..-28058
+on x=-65152..-11147,y=22489..91432,z=-58782..1780
+on x=-120100..-32970,y=-46592..27473,z=-11695..61039
+on x=-18631..37533,y=-124565..-50804,z=-35667..28308
+on x=-57817..18248,y=49321..117703,z=5745..55881
+on x=14781..98692,y=-1341..70827,z=15753..70151
+on x=-34419..55919,y=-19626..40991,z=39015..114138
+on x=-60785..11593,y=-56135..2999,z=-95368..-26915
+on x=-32178..58085,y=17647..101866,z=-91405..-8878
+on x=-53655..12091,y=50097..105568,z=-75335..-4862
+on x=-111166..-40997,y=-71714..2688,z=5609..50954
+on x=-16602..70118,y=-98693..-44401,z=5197..76897
+on x=16383..101554,y=4615..83635,z=-44907..18747
+off x=-95822..-15171,y=-19987..48940,z=10804..104439
+on x=-89813..-14614,y=16069..88491,z=-3297..45228
+on x=41075..99376,y=-20427..49978,z=-52012..13762
+on x=-21330..50085,y=-17944..62733,z=-112280..-30197
+on x=-16478..35915,y=36008..118594,z=-7885..47086
+off x=-98156..-27851,y=-49952..43171,z=-99005..-8456
+off x=2032..69770,y=-71013..4824,z=7471..94418
+on x=43670..120875,y=-42068..12382,z=-24787..38892
+off x=37514..111226,y=-45862..25743,z=-16714..54663
+off x=25699..97951,y=-30668..59918,z=-15349..69697
+off x=-44271..17935,y=-9516..60759,z=49131..112598
+on x=-61695..-5813,y=40978..94975,z=8655..80240
+off x=-101086..-9439,y=-7088..67543,z=33935..83858
+off x=18020..114017,y=-48931..32606,z=21474..89843
+off x=-77139..10506,y=-89994..-18797,z=-80..59318
+off x=8476..79288,y=-75520..11602,z=-96624..-24783
+on x=-47488..-1262,y=24338..100707,z=16292..72967
+off x=-84341..13987,y=2429..92914,z=-90671..-1318
+off x=-37810..49457,y=-71013..-7894,z=-105357..-13188
+off x=-27365..46395,y=31009..98017,z=15428..76570
+off x=-70369..-16548,y=22648..78696,z=-1892..86821
+on x=-53470..21291,y=-120233..-33476,z=-44150..38147
+off x=-93533..-4276,y=-16170..68771,z=-104985..-24507";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.len(), 4);
+        assert_eq!(input[0], (true, ((10, 10, 10), (12, 12, 12))));
+        assert_eq!(input[2], (false, ((9, 9, 9), (11, 11, 11))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cube() {
+        let mut cube = Cube::new((10, 10, 10), (12, 12, 12));
+        assert_eq!(cube.volume(), 27);
+
+        cube.subtract(&Cube::new((11, 11, 11), (13, 13, 13)));
+        assert_eq!(cube.volume(), 19);
+
+        cube.subtract(&Cube::new((9, 9, 9), (11, 11, 11)));
+        assert_eq!(cube.volume(), 12);
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), 39);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), 39);
+
+        let input = parse(REBOOT)?;
+        assert_eq!(part1(&input), 474140);
+        assert_eq!(part2(&input), 2758514936282235);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_params() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1_with(&input, &Params { region: 12 }), 20);
+        assert_eq!(part1_with(&input, &Params { region: u32::MAX }), 39);
+        assert!(with_overrides::<Params>(&[parse_override("region=-1")?]).is_err());
+
+        Ok(())
+    }
+}
@@ -1,95 +1,146 @@
+//! Day 3: Binary Diagnostic
+
+use crate::error::{lines, ParseError};
+use eyre::{bail, ContextCompat};
 use itertools::Itertools;
 use std::collections::HashMap;
 
+/// The diagnostic report, one binary number per line.
+pub type Input = Vec<String>;
+
+/// Parses the diagnostic report.
 #[aoc_generator(day3)]
-fn generator(input: &str) -> Vec<String> {
-    input.lines().map(String::from).collect()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let mut width = None;
+    lines(3, input)
+        .map(|line| {
+            let text = line.check_chars(line.text(), |c| c == '0' || c == '1', "expected a bit")?;
+
+            let width = *width.get_or_insert(text.len());
+            if text.len() != width {
+                let message = format!("expected {} digits, found {}", width, text.len());
+                return Err(line.error(text, message));
+            }
+
+            Ok(text.to_string())
+        })
+        .collect()
 }
 
+/// Computes the power consumption from the gamma and epsilon rates.
 #[aoc(day3, part1)]
-fn part1(input: &[String]) -> eyre::Result<u32> {
-    let input = (0..)
-        .map_while(|i| {
-            let counts = input
-                .iter()
-                .map(|line| line.chars().nth(i))
-                .collect::<Option<Vec<char>>>()?
-                .into_iter()
-                .counts();
-
-            Some(counts)
-        })
+pub fn part1(input: &[String]) -> eyre::Result<u64> {
+    let width = input.first().map(String::len).context("empty report")?;
+    let input = (0..width)
+        .map(|i| input.iter().filter_map(|line| line.chars().nth(i)).counts())
         .collect::<Vec<HashMap<char, usize>>>();
 
     let gamma = input
         .iter()
-        .map(|counts| {
-            let (&value, _) = counts.iter().max_by_key(|(_, &v)| v).unwrap();
-            value
+        .filter_map(|counts| {
+            let (&value, _) = counts.iter().max_by_key(|(_, &v)| v)?;
+            Some(value)
         })
         .collect::<String>();
     let gamma = u32::from_str_radix(&gamma, 2)?;
 
     let epsilon = input
         .iter()
-        .map(|counts| {
-            let (&value, _) = counts.iter().min_by_key(|(_, &v)| v).unwrap();
-            value
+        .filter_map(|counts| {
+            let (&value, _) = counts.iter().min_by_key(|(_, &v)| v)?;
+            Some(value)
         })
         .collect::<String>();
     let epsilon = u32::from_str_radix(&epsilon, 2)?;
 
-    Ok(gamma * epsilon)
+    Ok(u64::from(gamma) * u64::from(epsilon))
+}
+
+/// Narrows the report down to a single number, keeping the most or least
+/// common digit at each position. Ties keep `1` when keeping the most common
+/// digit and `0` otherwise.
+fn rating(input: &[Vec<char>], most_common: bool) -> eyre::Result<u32> {
+    let width = input.first().map(Vec::len).unwrap_or_default();
+    let candidates = (0..width).fold(input.to_vec(), |mut candidates, digit| {
+        if candidates.len() <= 1 {
+            return candidates;
+        }
+
+        let ones = candidates.iter().filter(|line| line[digit] == '1').count();
+        let zeros = candidates.len() - ones;
+        if ones == 0 || zeros == 0 {
+            return candidates;
+        }
+
+        let pick = if (ones >= zeros) == most_common {
+            '1'
+        } else {
+            '0'
+        };
+        candidates.retain(|candidate| candidate[digit] == pick);
+
+        candidates
+    });
+
+    match candidates.as_slice() {
+        [value] => Ok(u32::from_str_radix(&value.iter().collect::<String>(), 2)?),
+        _ => bail!("unable to narrow down to a single rating"),
+    }
 }
 
+/// Computes the life support rating from the oxygen generator and CO2 scrubber ratings.
 #[aoc(day3, part2)]
-fn part2(input: &[String]) -> eyre::Result<u32> {
+pub fn part2(input: &[String]) -> eyre::Result<u64> {
     let input = input
         .iter()
         .map(|line| line.chars().collect::<Vec<char>>())
         .collect::<Vec<Vec<char>>>();
 
-    let generator = (0..)
-        .try_fold(input.clone(), |mut candidates, digit| {
-            if candidates.len() == 1 {
-                let value = candidates[0].iter().collect::<String>();
-                return Err(value);
-            }
+    let generator = rating(&input, true)?;
+    let scrubber = rating(&input, false)?;
 
-            let counts = candidates.iter().map(|line| line[digit]).counts();
-            let pick = if counts[&'0'] == counts[&'1'] {
-                '1'
-            } else {
-                let (&pick, _) = counts.iter().max_by_key(|(_, &v)| v).unwrap();
-                pick
-            };
-            candidates.retain(|candidate| candidate[digit] == pick);
+    Ok(u64::from(generator) * u64::from(scrubber))
+}
 
-            Ok(candidates)
-        })
-        .unwrap_err();
-    let generator = u32::from_str_radix(&generator, 2)?;
-
-    let scrubber = (0..)
-        .try_fold(input, |mut candidates, digit| {
-            if candidates.len() == 1 {
-                let value = candidates[0].iter().collect::<String>();
-                return Err(value);
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let counts = candidates.iter().map(|line| line[digit]).counts();
-            let pick = if counts[&'0'] == counts[&'1'] {
-                '0'
-            } else {
-                let (&pick, _) = counts.iter().min_by_key(|(_, &v)| v).unwrap();
-                pick
-            };
-            candidates.retain(|candidate| candidate[digit] == pick);
+    const EXAMPLE: &str = "00100
+11110
+10110
+10111
+10101
+01111
+00111
+11100
+10000
+11001
+00010
+01010";
 
-            Ok(candidates)
-        })
-        .unwrap_err();
-    let scrubber = u32::from_str_radix(&scrubber, 2)?;
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.len(), 12);
+        assert_eq!(input[0], "00100");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input)?, 198);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input)?, 230);
 
-    Ok(generator * scrubber)
+        Ok(())
+    }
 }
@@ -1,14 +1,23 @@
+//! Day 4: Giant Squid
+
+use crate::{
+    error::{empty_input, lines, Line, ParseError},
+    params::define_params,
+    registry::{Answer, IntoAnswer},
+};
 use eyre::ContextCompat;
-use std::{collections::HashSet, num::ParseIntError};
+use std::{collections::HashSet, fmt};
 
-struct Input {
-    numbers: Vec<u32>,
-    boards: Vec<Board>,
+/// The called numbers and the bingo boards.
+pub struct Input {
+    pub numbers: Vec<u32>,
+    pub boards: Vec<Board>,
 }
 
+/// A square bingo board stored as rows of numbers.
 #[derive(Default)]
-struct Board {
-    rows: Vec<Vec<u32>>,
+pub struct Board {
+    pub rows: Vec<Vec<u32>>,
 }
 
 #[derive(Default)]
@@ -18,58 +27,113 @@ struct Game {
 }
 
 impl Board {
-    fn is_winner(&self, numbers: &HashSet<u32>) -> bool {
-        (0..5).any(|i| self.rows[i].iter().all(|n| numbers.contains(n)))
-            || (0..5).any(|j| {
+    /// Returns true if a full row or column has been called.
+    pub fn is_winner(&self, numbers: &HashSet<u32>) -> bool {
+        self.rows
+            .iter()
+            .any(|row| row.iter().all(|n| numbers.contains(n)))
+            || (0..self.rows.len()).any(|j| {
                 self.rows
                     .iter()
-                    .map(|row| row[j])
-                    .all(|n| numbers.contains(&n))
+                    .all(|row| row.get(j).is_some_and(|n| numbers.contains(n)))
             })
     }
 
-    fn score(&self, numbers: &HashSet<u32>) -> u32 {
+    /// Sums the numbers on the board that haven't been called.
+    pub fn score(&self, numbers: &HashSet<u32>) -> u64 {
         self.rows
             .iter()
             .flat_map(|row| row.iter())
             .filter(|n| !numbers.contains(n))
+            .copied()
+            .map(u64::from)
             .sum()
     }
 }
 
+define_params! {
+    /// The shape of the bingo boards.
+    pub struct BoardParams {
+        /// The number of rows on each board, and of numbers in each row.
+        pub size: usize = 5,
+    }
+}
+
+fn check_board(board: Option<&Board>, size: usize, line: Line) -> Result<(), ParseError> {
+    match board {
+        Some(board) if board.rows.len() != size => {
+            let message = format!("expected {} rows, found {}", size, board.rows.len());
+            Err(line.end_of_input(message))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parses the called numbers followed by blank line separated 5x5 boards.
 #[aoc_generator(day4)]
-fn generator(input: &str) -> eyre::Result<Input> {
-    let mut lines = input.lines();
-    let numbers = lines
-        .next()
-        .map(|s| {
-            s.split(',')
-                .map(str::parse)
-                .collect::<Result<_, ParseIntError>>()
-        })
-        .transpose()?
-        .context("unable to parse numbers")?;
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    parse_with(input, &BoardParams::default())
+}
 
-    let boards = lines.try_fold::<_, _, eyre::Result<_>>(vec![], |mut boards, line| {
-        if line.is_empty() {
-            boards.push(Board::default());
-            return Ok(boards);
+/// Parses the called numbers followed by blank line separated boards of
+/// `size` rows of `size` numbers.
+pub fn parse_with(input: &str, params: &BoardParams) -> Result<Input, ParseError> {
+    let size = params.size;
+    let mut lines = lines(4, input);
+    let first = lines.next().ok_or_else(|| empty_input(4))?;
+    let numbers = first
+        .text()
+        .split(',')
+        .map(|number| first.parse(number))
+        .collect::<Result<_, _>>()?;
+
+    let mut boards: Vec<Board> = vec![];
+    let mut previous = first;
+    let mut separator = None;
+    for line in lines {
+        // Only a row after a blank line starts a board, so trailing blank
+        // lines are ignored.
+        if line.text().is_empty() {
+            separator = Some(line);
+            continue;
         }
 
         let row = line
+            .text()
             .split_whitespace()
-            .map(str::parse)
-            .collect::<Result<_, ParseIntError>>()?;
-        let current_board = boards.last_mut().context("unable to find board")?;
+            .map(|number| line.parse(number))
+            .collect::<Result<Vec<_>, _>>()?;
+        if row.is_empty() {
+            return Err(line.error(line.text(), "expected a row of numbers"));
+        }
+        if row.len() != size {
+            let message = format!("expected {} numbers, found {}", size, row.len());
+            return Err(line.error(line.text(), message));
+        }
+
+        if let Some(separator) = separator.take() {
+            check_board(boards.last(), size, separator)?;
+            boards.push(Board::default());
+        }
+        let current_board = boards
+            .last_mut()
+            .ok_or_else(|| line.error(line.text(), "expected a blank line before the board"))?;
+        if current_board.rows.len() == size {
+            let message = format!("expected a blank line after {} rows", size);
+            return Err(line.error(line.text(), message));
+        }
         current_board.rows.push(row);
+        previous = line;
+    }
 
-        Ok(boards)
-    })?;
+    check_board(boards.last(), size, previous)?;
 
     Ok(Input { numbers, boards })
 }
 
-fn game_stream(input: &Input) -> impl Iterator<Item = u32> + '_ {
+/// Yields the final score of each board as it wins, or an error if a score
+/// overflows.
+fn game_stream(input: &Input) -> impl Iterator<Item = eyre::Result<u64>> + '_ {
     input
         .numbers
         .iter()
@@ -88,19 +152,137 @@ fn game_stream(input: &Input) -> impl Iterator<Item = u32> + '_ {
 
             let scores = round_winners
                 .into_iter()
-                .map(|board_index| number * input.boards[board_index].score(&game.called_numbers))
-                .collect::<Vec<u32>>();
+                .map(|board_index| {
+                    input.boards[board_index]
+                        .score(&game.called_numbers)
+                        .checked_mul(u64::from(number))
+                        .context("score overflowed")
+                })
+                .collect::<Vec<_>>();
             Some(scores)
         })
         .flatten()
 }
 
+/// The final score of a winning board, or `None` if no board wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Winner(pub Option<u64>);
+
+impl fmt::Display for Winner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(score) => write!(f, "{}", score),
+            None => write!(f, "no board wins"),
+        }
+    }
+}
+
+impl IntoAnswer for Winner {
+    fn into_answer(self) -> Answer {
+        self.0.into_answer()
+    }
+}
+
+/// Scores the first board to win.
 #[aoc(day4, part1)]
-fn part1(input: &Input) -> Option<u32> {
-    game_stream(input).next()
+pub fn part1(input: &Input) -> eyre::Result<Winner> {
+    game_stream(input).next().transpose().map(Winner)
 }
 
+/// Scores the last board to win.
 #[aoc(day4, part2)]
-fn part2(input: &Input) -> Option<u32> {
-    game_stream(input).last()
+pub fn part2(input: &Input) -> eyre::Result<Winner> {
+    game_stream(input).last().transpose().map(Winner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.numbers.len(), 27);
+        assert_eq!(input.boards.len(), 3);
+        assert_eq!(input.boards[1].rows[0], [3, 15, 0, 2, 22]);
+        assert_eq!(input.boards[2].rows[4], [2, 0, 12, 3, 7]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input)?, Winner(Some(4512)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input)?, Winner(Some(1924)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_board_size() -> eyre::Result<()> {
+        let small = "7,4,1\n\n1 2 3\n4 5 6\n7 8 9";
+        let input = parse_with(small, &BoardParams { size: 3 })?;
+        assert_eq!(part1(&input)?, Winner(Some(33)));
+
+        let error = parse(small).err().context("expected an error")?;
+        assert_eq!((error.line, error.column), (3, 1));
+        assert_eq!(error.message, "expected 5 numbers, found 3");
+
+        let mixed = "1\n\n1 2\n3 4\n\n1 2 3\n4 5 6\n7 8 9";
+        let error = parse_with(mixed, &BoardParams { size: 2 })
+            .err()
+            .context("expected an error")?;
+        assert_eq!(error.line, 6);
+        assert!(parse_with("1\n\n1 2 3\n4 5 6", &BoardParams { size: 3 }).is_err());
+
+        let trailing = parse(&format!("{}\n\n\n", EXAMPLE))?;
+        assert_eq!(trailing.boards.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_winner() -> eyre::Result<()> {
+        let params = BoardParams { size: 2 };
+        let input = parse_with("1\n\n2 3\n4 5", &params)?;
+        assert_eq!(part1(&input)?, Winner(None));
+        assert_eq!(part2(&input)?.into_answer(), Answer::None);
+
+        let input = parse_with(
+            "4294967295,4294967293\n\n4294967295 4294967294\n4294967293 4294967292",
+            &params,
+        )?;
+        let error = part1(&input).err().context("expected an error")?;
+        assert_eq!(error.to_string(), "score overflowed");
+
+        Ok(())
+    }
 }
@@ -1,30 +1,32 @@
-use eyre::ContextCompat;
+//! Day 5: Hydrothermal Venture
+
+use crate::error::{self, lines, ParseError};
 use std::collections::HashMap;
 
-type Point = (i16, i16);
-type Line = (Point, Point);
+pub type Point = (i16, i16);
+/// A vent line from one end point to the other.
+pub type Line = (Point, Point);
+
+/// The vent lines, in order.
+pub type Input = Vec<Line>;
 
+/// Parses one `x1,y1 -> x2,y2` vent line per line.
 #[aoc_generator(day5)]
-fn generator(input: &str) -> eyre::Result<Vec<Line>> {
-    input
-        .lines()
-        .map(|line| {
-            let (left, right) = line.split_once(" -> ").context("unable to split line")?;
-            let (x1, y1) = left
-                .split_once(',')
-                .context("unable to split left points")?;
-            let (x2, y2) = right
-                .split_once(',')
-                .context("unable to split right points")?;
-
-            let x1 = x1.parse()?;
-            let y1 = y1.parse()?;
-            let x2 = x2.parse()?;
-            let y2 = y2.parse()?;
-
-            Ok(((x1, y1), (x2, y2)))
-        })
-        .collect()
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    lines(5, input).map(vent_line).collect()
+}
+
+fn vent_line(line: error::Line) -> Result<Line, ParseError> {
+    let (left, right) = line.split_once(" -> ")?;
+    let (x1, y1) = line.split_span(left, ",")?;
+    let (x2, y2) = line.split_span(right, ",")?;
+
+    let x1 = line.parse(x1)?;
+    let y1 = line.parse(y1)?;
+    let x2 = line.parse(x2)?;
+    let y2 = line.parse(y2)?;
+
+    Ok(((x1, y1), (x2, y2)))
 }
 
 fn solve<P>(input: &[Line], predicate: P) -> usize
@@ -57,14 +59,67 @@ where
         .count()
 }
 
+/// Counts the points where at least two horizontal or vertical lines overlap.
 #[aoc(day5, part1)]
-fn part1(input: &[Line]) -> usize {
+pub fn part1(input: &[Line]) -> usize {
     solve(input, |_, (x1, y1), (x2, y2)| (x1 == x2) || (y1 == y2))
 }
 
+/// Counts the points where at least two lines overlap, including diagonals.
 #[aoc(day5, part2)]
-fn part2(input: &[Line]) -> usize {
+pub fn part2(input: &[Line]) -> usize {
     solve(input, |(x, y), (x1, y1), (x2, y2)| {
-        (x1 == x2) || (y1 == y2) || (x - x1).abs() == (y - y1).abs()
+        let dx = (i32::from(x) - i32::from(x1)).abs();
+        let dy = (i32::from(y) - i32::from(y1)).abs();
+        (x1 == x2) || (y1 == y2) || dx == dy
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.len(), 10);
+        assert_eq!(input[0], ((0, 9), (5, 9)));
+        assert_eq!(input[9], ((5, 5), (8, 2)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse("0,9 -> 5,9\n8,0 -> 0;8").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 8));
+        assert_eq!(error.message, "expected \",\"");
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), 12);
+
+        Ok(())
+    }
+}
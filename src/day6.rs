@@ -1,47 +1,126 @@
-use eyre::{eyre, ContextCompat};
+//! Day 6: Lanternfish
 
+use crate::{
+    error::{empty_input, lines, ParseError},
+    params::define_params,
+};
+
+/// The internal timer of each lanternfish.
+pub type Input = Vec<usize>;
+
+/// Parses the comma separated list of timers.
 #[aoc_generator(day6)]
-fn generator(input: &str) -> eyre::Result<Vec<usize>> {
-    input
-        .lines()
-        .next()
-        .context("unable to read input")?
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let line = lines(6, input).next().ok_or_else(|| empty_input(6))?;
+    line.text()
         .split(',')
-        .map(|value| value.parse().map_err(|_| eyre!("unable to parse number")))
+        .map(|value| match line.parse(value)? {
+            timer @ 0..=8 => Ok(timer),
+            _ => Err(line.error(value, "timer must be between 0 and 8")),
+        })
         .collect()
 }
 
-fn solve(input: &[usize], rounds: usize) -> usize {
-    let mut population = input.iter().fold([0; 9], |mut acc, &cycle| {
-        acc[cycle as usize] += 1;
+define_params! {
+    /// How long the lanternfish are left to multiply.
+    pub struct Params {
+        /// Days simulated by part 1.
+        pub part1_days: usize = 80,
+        /// Days simulated by part 2.
+        pub part2_days: usize = 256,
+    }
+}
+
+/// Counts the lanternfish after `rounds` days, or `None` if the count
+/// overflows.
+pub fn solve(input: &[usize], rounds: usize) -> Option<usize> {
+    let mut population = input.iter().fold([0usize; 9], |mut acc, &cycle| {
+        if let Some(count) = acc.get_mut(cycle) {
+            *count += 1;
+        }
         acc
     });
 
     for _ in 0..rounds {
-        let mut next = [0; 9];
-        for (cycle, &count) in population.iter().enumerate() {
-            let cycle = if cycle == 0 {
-                next[8] += count;
-                6
-            } else {
-                cycle - 1
-            };
-
-            next[cycle] += count;
-        }
-
-        population = next;
+        population.rotate_left(1);
+        population[6] = population[6].checked_add(population[8])?;
     }
 
-    population.iter().sum()
+    population
+        .iter()
+        .try_fold(0usize, |total, &count| total.checked_add(count))
 }
 
+/// Counts the lanternfish after 80 days.
 #[aoc(day6, part1)]
-fn part1(input: &[usize]) -> usize {
-    solve(input, 80)
+pub fn part1(input: &[usize]) -> Option<usize> {
+    part1_with(input, &Params::default())
 }
 
+/// Counts the lanternfish after `part1_days` days.
+pub fn part1_with(input: &[usize], params: &Params) -> Option<usize> {
+    solve(input, params.part1_days)
+}
+
+/// Counts the lanternfish after 256 days.
 #[aoc(day6, part2)]
-fn part2(input: &[usize]) -> usize {
-    solve(input, 256)
+pub fn part2(input: &[usize]) -> Option<usize> {
+    part2_with(input, &Params::default())
+}
+
+/// Counts the lanternfish after `part2_days` days.
+pub fn part2_with(input: &[usize], params: &Params) -> Option<usize> {
+    solve(input, params.part2_days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "3,4,3,1,2";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input, [3, 4, 3, 1, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(solve(&input, 18), Some(26));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), Some(5934));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), Some(26984457539));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_params() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let params = Params {
+            part1_days: 18,
+            ..Params::default()
+        };
+        assert_eq!(part1_with(&input, &params), Some(26));
+        assert_eq!(solve(&input, 100_000), None);
+
+        Ok(())
+    }
 }
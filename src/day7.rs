@@ -1,43 +1,77 @@
-use eyre::{eyre, ContextCompat};
+//! Day 7: The Treachery of Whales
+
+use crate::error::{empty_input, lines, ParseError};
 use itertools::Itertools;
 
+/// The horizontal position of each crab.
+pub type Input = Vec<i32>;
+
+/// Parses the comma separated list of positions.
 #[aoc_generator(day7)]
-fn generator(input: &str) -> eyre::Result<Vec<i32>> {
-    input
-        .lines()
-        .next()
-        .context("unable to read input")?
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let line = lines(7, input).next().ok_or_else(|| empty_input(7))?;
+    line.text()
         .split(',')
-        .map(|value| value.parse().map_err(|_| eyre!("unable to parse number")))
+        .map(|value| line.parse(value))
         .collect()
 }
 
-fn solve<F>(input: &[i32], cost: F) -> Option<i32>
+/// Finds the cheapest destination. Costs that overflow can never be the
+/// cheapest, so those destinations are skipped.
+fn solve<F>(input: &[i32], cost: F) -> Option<i64>
 where
-    F: Fn(i32, i32) -> i32,
+    F: Fn(i64) -> Option<i64>,
 {
     let (&start, &end) = input.iter().minmax().into_option()?;
     (start..=end)
-        .map(|destination| {
-            input
-                .iter()
-                .map(|&position| cost(destination, position))
-                .sum()
+        .filter_map(|destination| {
+            input.iter().try_fold(0i64, |total, &position| {
+                let steps = (i64::from(position) - i64::from(destination)).abs();
+                total.checked_add(cost(steps)?)
+            })
         })
         .min()
 }
 
+/// Finds the least fuel needed to align, with a constant cost per step.
 #[aoc(day7, part1)]
-fn part1(input: &[i32]) -> Option<i32> {
-    solve(input, |destination, position| {
-        (position - destination).abs()
-    })
+pub fn part1(input: &[i32]) -> Option<i64> {
+    solve(input, Some)
 }
 
+/// Finds the least fuel needed to align, with a cost that grows each step.
 #[aoc(day7, part2)]
-fn part2(input: &[i32]) -> Option<i32> {
-    solve(input, |destination, position| {
-        let steps = (position - destination).abs();
-        steps * (steps + 1) / 2
-    })
+pub fn part2(input: &[i32]) -> Option<i64> {
+    solve(input, |steps| Some(steps.checked_mul(steps + 1)? / 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "16,1,2,0,4,2,7,1,2,14";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input, [16, 1, 2, 0, 4, 2, 7, 1, 2, 14]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), Some(37));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), Some(168));
+
+        Ok(())
+    }
 }
@@ -1,25 +1,48 @@
+//! Day 8: Seven Segment Search
+
+use crate::error::{lines, Line, ParseError};
 use itertools::Itertools;
 use std::collections::HashSet;
 
-type Numeral = HashSet<char>;
+/// The lit segments of a single digit.
+pub type Numeral = HashSet<char>;
+
+/// The ten unique signal patterns and the four output digits of a display.
+pub type Entry = (Vec<Numeral>, Vec<Numeral>);
 
+/// One entry per display.
+pub type Input = Vec<Entry>;
+
+/// Parses one `patterns | output` entry per line.
 #[aoc_generator(day8)]
-fn generator(input: &str) -> Option<Vec<(Vec<Numeral>, Vec<Numeral>)>> {
-    input
-        .lines()
-        .map(|line| {
-            let (input, output) = line.split_once(" | ")?;
-            let input = input
-                .split_ascii_whitespace()
-                .map(|value| value.chars().collect())
-                .collect();
-            let output = output
-                .split_ascii_whitespace()
-                .map(|value| value.chars().collect())
-                .collect();
-            Some((input, output))
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    lines(8, input).map(entry).collect()
+}
+
+fn numerals<'a>(line: &Line<'a>, span: &'a str, count: usize) -> Result<Vec<Numeral>, ParseError> {
+    let numerals = span
+        .split_ascii_whitespace()
+        .map(|value| {
+            let value =
+                line.check_chars(value, |c| ('a'..='g').contains(&c), "expected a segment")?;
+            Ok(value.chars().collect())
         })
-        .collect()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if numerals.len() != count {
+        let message = format!("expected {} patterns, found {}", count, numerals.len());
+        return Err(line.error(span, message));
+    }
+
+    Ok(numerals)
+}
+
+fn entry(line: Line) -> Result<Entry, ParseError> {
+    let (input, output) = line.split_once(" | ")?;
+    let input = numerals(&line, input, 10)?;
+    let output = numerals(&line, output, 4)?;
+
+    Ok((input, output))
 }
 
 fn find_segments(input: &[Numeral]) -> Option<[Numeral; 10]> {
@@ -78,8 +101,9 @@ fn find_segments(input: &[Numeral]) -> Option<[Numeral; 10]> {
     Some(known)
 }
 
+/// Counts the output digits that use a unique number of segments.
 #[aoc(day8, part1)]
-fn part1(input: &[(Vec<Numeral>, Vec<Numeral>)]) -> usize {
+pub fn part1(input: &[Entry]) -> usize {
     input
         .iter()
         .flat_map(|(_, output)| {
@@ -90,8 +114,9 @@ fn part1(input: &[(Vec<Numeral>, Vec<Numeral>)]) -> usize {
         .count()
 }
 
+/// Decodes every display and sums the output values.
 #[aoc(day8, part2)]
-fn part2(input: &[(Vec<Numeral>, Vec<Numeral>)]) -> Option<usize> {
+pub fn part2(input: &[Entry]) -> Option<usize> {
     input
         .iter()
         .map(|(input, output)| {
@@ -108,3 +133,69 @@ fn part2(input: &[(Vec<Numeral>, Vec<Numeral>)]) -> Option<usize> {
         })
         .sum()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::ContextCompat;
+
+    const EXAMPLE: &str =
+        "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.len(), 10);
+
+        let (patterns, output) = &input[0];
+        assert_eq!(patterns.len(), 10);
+        assert_eq!(output.len(), 4);
+        assert_eq!(output[3], "gcbe".chars().collect());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_segments() -> eyre::Result<()> {
+        let input = parse(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )?;
+        let known = find_segments(&input[0].0).context("unable to find segments")?;
+
+        let expected = [
+            "cagedb", "ab", "gcdfa", "fbcad", "eafb", "cdfbe", "cdfgeb", "dab", "acedgfb", "cefabd",
+        ];
+        for (digit, segments) in expected.into_iter().enumerate() {
+            assert_eq!(known[digit], segments.chars().collect(), "digit {}", digit);
+        }
+
+        assert_eq!(part2(&input), Some(5353));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), 26);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), Some(61229));
+
+        Ok(())
+    }
+}
@@ -1,51 +1,41 @@
-use std::collections::{HashMap, HashSet};
+//! Day 9: Smoke Basin
 
-type Point = (i16, i16);
+use crate::{
+    error::{lines, ParseError},
+    grid::{Grid, Point},
+};
+use std::collections::HashSet;
 
-#[aoc_generator(day9)]
-fn generator(input: &str) -> Option<HashMap<Point, u16>> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(x, c)| c.to_digit(10).map(|n| ((x as i16, y as i16), n as u16)))
-        })
-        .collect()
-}
+/// The height map of the cave floor.
+pub type Input = Grid<u16>;
 
-fn neighbors((x, y): Point) -> impl Iterator<Item = Point> {
-    [(0, -1), (-1, 0), (1, 0), (0, 1)]
-        .into_iter()
-        .map(move |(dx, dy)| (x + dx, y + dy))
+/// Parses the height map, one digit per location.
+#[aoc_generator(day9)]
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    Grid::parse_digits(lines(9, input))
 }
 
-fn low_points(input: &HashMap<Point, u16>) -> impl Iterator<Item = Point> + '_ {
+fn low_points(input: &Input) -> impl Iterator<Item = Point> + '_ {
     input
         .iter()
-        .filter(|&(&point, &height)| {
-            neighbors(point)
-                .filter_map(|neighbor| input.get(&neighbor))
-                .all(|&neighbor_height| neighbor_height > height)
+        .filter(|&(point, &height)| {
+            input
+                .neighbors4(point)
+                .all(|neighbor| input[neighbor] > height)
         })
-        .map(|(&point, _)| point)
+        .map(|(point, _)| point)
 }
 
-fn basin_size(start: Point, input: &HashMap<Point, u16>) -> usize {
+fn basin_size(start: Point, input: &Input) -> usize {
     let mut visited = HashSet::new();
     let mut search = vec![start];
 
     while let Some(point) = search.pop() {
         visited.insert(point);
 
-        let basin_neighbors = neighbors(point).filter(|neighbor| {
-            match (visited.contains(neighbor), input.get(neighbor)) {
-                (true, _) => false,
-                (_, Some(&h)) => h < 9,
-                _ => false,
-            }
-        });
+        let basin_neighbors = input
+            .neighbors4(point)
+            .filter(|neighbor| !visited.contains(neighbor) && input[*neighbor] < 9);
 
         search.extend(basin_neighbors);
     }
@@ -53,20 +43,80 @@ fn basin_size(start: Point, input: &HashMap<Point, u16>) -> usize {
     visited.len()
 }
 
+/// Sums the risk levels of the low points.
 #[aoc(day9, part1)]
-fn part1(input: &HashMap<Point, u16>) -> u16 {
+pub fn part1(input: &Input) -> usize {
     low_points(input)
-        .filter_map(|point| input.get(&point))
-        .map(|&height| height + 1)
+        .map(|point| usize::from(input[point]) + 1)
         .sum()
 }
 
+/// Multiplies the sizes of the three largest basins.
 #[aoc(day9, part2)]
-fn part2(input: &HashMap<Point, u16>) -> usize {
+pub fn part2(input: &Input) -> Option<usize> {
     let mut basin_sizes = low_points(input)
         .map(|point| basin_size(point, input))
         .collect::<Vec<usize>>();
 
     basin_sizes.sort_unstable();
-    basin_sizes.into_iter().rev().take(3).product()
+    basin_sizes
+        .into_iter()
+        .rev()
+        .take(3)
+        .try_fold(1usize, |product, size| product.checked_mul(size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2199943210
+3987894921
+9856789892
+8767896789
+9899965678";
+
+    #[test]
+    fn test_parse() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(input.width(), 10);
+        assert_eq!(input.height(), 5);
+        assert_eq!(input[(1, 0)], 1);
+        assert_eq!(input[(9, 4)], 8);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_low_points() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        let mut points = low_points(&input).collect::<Vec<Point>>();
+        points.sort_unstable();
+        assert_eq!(points, [(1, 0), (2, 2), (6, 4), (9, 0)]);
+
+        let mut sizes = points
+            .into_iter()
+            .map(|point| basin_size(point, &input))
+            .collect::<Vec<usize>>();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [3, 9, 9, 14]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part1(&input), 15);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> eyre::Result<()> {
+        let input = parse(EXAMPLE)?;
+        assert_eq!(part2(&input), Some(1134));
+
+        Ok(())
+    }
 }
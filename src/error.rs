@@ -0,0 +1,179 @@
+//! The error type shared by every day's generator.
+
+use std::{error::Error, fmt, str::FromStr};
+
+/// A malformed puzzle input, pointing at the offending text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    /// The line the error was found on, starting at 1.
+    pub line: usize,
+    /// The character within the line the error starts at, starting at 1.
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {} line {} column {}: {} at {:?}",
+            self.day, self.line, self.column, self.message, self.text
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// A single line of puzzle input, used to report errors at their position in
+/// the input.
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    day: u8,
+    number: usize,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(day: u8, number: usize, text: &'a str) -> Self {
+        Self { day, number, text }
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Builds an error pointing at `span`, which should be a slice of this
+    /// line. Spans from elsewhere point at the start of the line.
+    pub fn error<M: Into<String>>(&self, span: &str, message: M) -> ParseError {
+        let start = self.text.as_ptr() as usize;
+        let offset = (span.as_ptr() as usize)
+            .checked_sub(start)
+            .filter(|&offset| offset <= self.text.len())
+            .unwrap_or_default();
+        let column = self.text[..offset].chars().count() + 1;
+
+        ParseError {
+            day: self.day,
+            line: self.number,
+            column,
+            text: span.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Parses `span`, reporting the parser's error at the span's position.
+    pub fn parse<T>(&self, span: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        span.parse()
+            .map_err(|e: T::Err| self.error(span, e.to_string()))
+    }
+
+    /// Splits the line around the first `delimiter`.
+    pub fn split_once(&self, delimiter: &str) -> Result<(&'a str, &'a str), ParseError> {
+        split_once(self, self.text, delimiter)
+    }
+
+    /// Splits `span` around the first `delimiter`, reporting a missing
+    /// delimiter at the span's position.
+    pub fn split_span(
+        &self,
+        span: &'a str,
+        delimiter: &str,
+    ) -> Result<(&'a str, &'a str), ParseError> {
+        split_once(self, span, delimiter)
+    }
+
+    /// Checks that every character of `span` is accepted by `valid`,
+    /// reporting the first one that isn't.
+    pub fn check_chars<P>(
+        &self,
+        span: &'a str,
+        valid: P,
+        message: &str,
+    ) -> Result<&'a str, ParseError>
+    where
+        P: Fn(char) -> bool,
+    {
+        match span.char_indices().find(|&(_, c)| !valid(c)) {
+            Some((offset, c)) => Err(self.error(&span[offset..offset + c.len_utf8()], message)),
+            None => Ok(span),
+        }
+    }
+
+    /// An error for input that ends before `message` was satisfied, pointing
+    /// just past this line.
+    pub fn end_of_input<M: Into<String>>(&self, message: M) -> ParseError {
+        self.error(&self.text[self.text.len()..], message)
+    }
+}
+
+fn split_once<'a>(
+    line: &Line<'a>,
+    span: &'a str,
+    delimiter: &str,
+) -> Result<(&'a str, &'a str), ParseError> {
+    span.split_once(delimiter)
+        .ok_or_else(|| line.error(span, format!("expected {:?}", delimiter)))
+}
+
+/// Numbers the lines of `input`, starting at 1.
+pub fn lines(day: u8, input: &str) -> impl Iterator<Item = Line<'_>> {
+    input
+        .lines()
+        .enumerate()
+        .map(move |(index, text)| Line::new(day, index + 1, text))
+}
+
+/// An error for input with no lines at all.
+pub fn empty_input(day: u8) -> ParseError {
+    ParseError {
+        day,
+        line: 1,
+        column: 1,
+        text: String::new(),
+        message: "empty input".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_column() {
+        let line = Line::new(5, 3, "0,9 -> x,9");
+        let (_, right) = line.split_once(" -> ").unwrap();
+        let (x, _) = line.split_span(right, ",").unwrap();
+
+        let error = line.parse::<i16>(x).unwrap_err();
+        assert_eq!(error.day, 5);
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 8);
+        assert_eq!(error.text, "x");
+        assert_eq!(
+            error.to_string(),
+            "day 5 line 3 column 8: invalid digit found in string at \"x\""
+        );
+    }
+
+    #[test]
+    fn test_missing_delimiter() {
+        let line = Line::new(12, 1, "start");
+        let error = line.split_once("-").unwrap_err();
+        assert_eq!(error.column, 1);
+        assert_eq!(error.message, "expected \"-\"");
+    }
+}
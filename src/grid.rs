@@ -0,0 +1,299 @@
+//! A dense two dimensional grid shared by the grid based puzzles.
+
+use crate::error::{Line, ParseError};
+use std::ops::{Index, IndexMut};
+
+/// A position in a [`Grid`] as `(x, y)`, with `(0, 0)` at the top left.
+pub type Point = (usize, usize);
+
+const CARDINAL: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+fn offset_within(
+    width: usize,
+    height: usize,
+    (x, y): Point,
+    (dx, dy): (isize, isize),
+) -> Option<Point> {
+    let x = x.checked_add_signed(dx)?;
+    let y = y.checked_add_signed(dy)?;
+    if x < width && y < height {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// A dense, row-major two dimensional grid.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from row-major cells, returning `None` if the number of
+    /// cells doesn't match the dimensions.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Option<Self> {
+        if width.checked_mul(height)? != cells.len() {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn from_fn<F>(width: usize, height: usize, mut f: F) -> Self
+    where
+        F: FnMut(Point) -> T,
+    {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(&mut f)
+            .collect();
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Parses one cell per character, one row per line. Rows must all have
+    /// the same length.
+    pub fn parse_chars<'a, I, F>(lines: I, mut f: F) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = Line<'a>>,
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+
+        for line in lines {
+            let text = line.text();
+            for (offset, c) in text.char_indices() {
+                match f(c) {
+                    Some(cell) => cells.push(cell),
+                    None => {
+                        let span = &text[offset..offset + c.len_utf8()];
+                        return Err(line.error(span, "unexpected character"));
+                    }
+                }
+            }
+
+            let row_width = text.chars().count();
+            let width = *width.get_or_insert(row_width);
+            if row_width != width {
+                let message = format!("expected {} cells, found {}", width, row_width);
+                return Err(line.error(text, message));
+            }
+
+            height += 1;
+        }
+
+        Ok(Self {
+            width: width.unwrap_or_default(),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, (x, y): Point) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        if self.contains(point) {
+            Some(&self.cells[self.index_of(point)])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        if self.contains(point) {
+            let index = self.index_of(point);
+            Some(&mut self.cells[index])
+        } else {
+            None
+        }
+    }
+
+    /// Moves `point` by `(dx, dy)`, returning `None` if the result falls
+    /// outside of the grid.
+    pub fn offset(&self, point: Point, delta: (isize, isize)) -> Option<Point> {
+        offset_within(self.width, self.height, point, delta)
+    }
+
+    /// The up to four points directly above, left, right and below `point`.
+    ///
+    /// The iterator doesn't borrow the grid, so cells can be updated while
+    /// walking the neighbors.
+    pub fn neighbors4(&self, point: Point) -> impl Iterator<Item = Point> {
+        let (width, height) = (self.width, self.height);
+        CARDINAL
+            .into_iter()
+            .filter_map(move |delta| offset_within(width, height, point, delta))
+    }
+
+    /// The up to eight points surrounding `point`, including diagonals.
+    pub fn neighbors8(&self, point: Point) -> impl Iterator<Item = Point> {
+        let (width, height) = (self.width, self.height);
+        SURROUNDING
+            .into_iter()
+            .filter_map(move |delta| offset_within(width, height, point, delta))
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        if y < self.height {
+            let start = y * self.width;
+            Some(&self.cells[start..start + self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).filter_map(|y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &T>> {
+        if x < self.width {
+            Some(self.cells.iter().skip(x).step_by(self.width))
+        } else {
+            None
+        }
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).filter_map(|x| self.column(x))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    fn index_of(&self, (x, y): Point) -> usize {
+        y * self.width + x
+    }
+}
+
+impl<T: From<u8>> Grid<T> {
+    /// Parses a block of decimal digits, one cell per digit.
+    pub fn parse_digits<'a, I>(lines: I) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = Line<'a>>,
+    {
+        Self::parse_chars(lines, |c| c.to_digit(10).map(|n| T::from(n as u8)))
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &Self::Output {
+        self.get(point).expect("point outside of grid")
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut Self::Output {
+        self.get_mut(point).expect("point outside of grid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::lines;
+
+    #[test]
+    fn test_parse_digits() {
+        let grid = Grid::<u8>::parse_digits(lines(0, "123\n456")).unwrap();
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid[(2, 1)], 6);
+        assert_eq!(grid.row(1), Some(&[4, 5, 6][..]));
+        assert_eq!(
+            grid.column(1).unwrap().copied().collect::<Vec<u8>>(),
+            vec![2, 5]
+        );
+
+        let error = Grid::<u8>::parse_digits(lines(0, "123\n45")).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+
+        let error = Grid::<u8>::parse_digits(lines(0, "123\n1x3")).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+        assert_eq!(error.text, "x");
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = Grid::from_fn(3, 3, |_| 0);
+
+        assert_eq!(
+            grid.neighbors4((0, 0)).collect::<Vec<_>>(),
+            [(1, 0), (0, 1)]
+        );
+        assert_eq!(grid.neighbors4((1, 1)).count(), 4);
+        assert_eq!(grid.neighbors8((0, 0)).count(), 3);
+        assert_eq!(grid.neighbors8((1, 1)).count(), 8);
+        assert_eq!(grid.neighbors8((2, 1)).count(), 5);
+    }
+}
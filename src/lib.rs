@@ -1,27 +1,48 @@
+//! Solutions to [Advent of Code 2021](https://adventofcode.com/2021).
+//!
+//! Each day lives in its own module and exposes the same shape of API: an
+//! `Input` type for the parsed puzzle input, a `parse` function that builds
+//! it from the puzzle text and `part1`/`part2` functions that solve each half
+//! of the puzzle. These are the same functions `cargo aoc` runs.
+//!
+//! ```
+//! use advent_2021::day1;
+//!
+//! let input = day1::parse("199\n200\n208\n210\n200\n207\n240\n269\n260\n263").unwrap();
+//! assert_eq!(day1::part1(&input), 7);
+//! assert_eq!(day1::part2(&input), 5);
+//! ```
+
 #[macro_use]
 extern crate aoc_runner_derive;
 
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day2;
-mod day20;
-mod day21;
-mod day22;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+pub mod answers;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod error;
+pub mod grid;
+pub mod params;
+pub mod registry;
+pub mod report;
 
 aoc_lib! { year = 2021 }
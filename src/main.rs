@@ -0,0 +1,220 @@
+use advent_2021::{
+    answers::Manifest,
+    day16::{self, Disassembly},
+    day19,
+    params::{parse_override, Override},
+    registry::{self, Solution},
+    report::{self, Report},
+};
+use clap::{ArgEnum, Parser, Subcommand};
+use eyre::{bail, ContextCompat, WrapErr};
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Runs Advent of Code 2021 solutions against a puzzle input.
+#[derive(Parser)]
+#[clap(
+    name = "advent-2021",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Day to run, from 1 to 22, runs every day when omitted
+    #[clap(short, long)]
+    day: Option<u8>,
+
+    /// Part to run, runs both parts when omitted
+    #[clap(short, long)]
+    part: Option<u8>,
+
+    /// Puzzle input file, or - to read from stdin. Without --day, a
+    /// directory holding a dayN.txt input for each day
+    #[clap(required = true)]
+    input: Option<PathBuf>,
+
+    /// Adds the confirmed answers to this answers manifest
+    #[clap(long, value_name = "MANIFEST")]
+    record: Option<PathBuf>,
+
+    /// Overrides one of the day's puzzle parameters
+    #[clap(long = "param", value_name = "KEY=VALUE", parse(try_from_str = parse_override))]
+    params: Vec<Override>,
+
+    /// Prints a report of every answer with its timings instead of the bare
+    /// answers
+    #[clap(long, arg_enum, value_name = "FORMAT")]
+    report: Option<Format>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists every packet of a day 16 transmission with its bit offset
+    Disassemble {
+        /// Transmission file, or - to read from stdin
+        input: PathBuf,
+
+        /// Prints the transmission as an infix expression instead
+        #[clap(long)]
+        infix: bool,
+    },
+    /// Exports the beacons and scanner positions of a day 19 report, aligned
+    /// with scanner 0
+    Map {
+        /// Scanner report file, or - to read from stdin
+        input: PathBuf,
+
+        /// Output format, with PLY giving a point cloud for 3D viewers
+        #[clap(long, arg_enum, value_name = "FORMAT", default_value = "json")]
+        format: MapFormat,
+    },
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum MapFormat {
+    Json,
+    Csv,
+    Ply,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+fn read_input(path: &Path) -> eyre::Result<String> {
+    if path.as_os_str() == "-" {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .wrap_err("unable to read stdin")?;
+        Ok(input)
+    } else {
+        fs::read_to_string(path).wrap_err_with(|| format!("unable to read {}", path.display()))
+    }
+}
+
+/// Reads the input for every day that will be run.
+fn read_inputs(
+    args: &Args,
+    path: &Path,
+    solutions: &[&Solution],
+) -> eyre::Result<BTreeMap<u8, String>> {
+    let mut inputs = BTreeMap::new();
+    for solution in solutions {
+        if let Entry::Vacant(entry) = inputs.entry(solution.day) {
+            let input = match args.day {
+                Some(_) => read_input(path)?,
+                None => read_input(&path.join(format!("day{}.txt", solution.day)))?,
+            };
+            entry.insert(input);
+        }
+    }
+
+    Ok(inputs)
+}
+
+fn run_command(command: &Command) -> eyre::Result<()> {
+    match command {
+        Command::Disassemble { input, infix } => {
+            let input = read_input(input)?;
+            if *infix {
+                println!("{}", day16::parse(&input)?);
+            } else {
+                print!("{}", Disassembly::new(&input)?);
+            }
+        }
+        Command::Map { input, format } => {
+            let map = day19::Map::new(&day19::parse(&read_input(input)?)?);
+            let stdout = io::stdout();
+            match format {
+                MapFormat::Json => map.write_json(stdout.lock())?,
+                MapFormat::Csv => map.write_csv(stdout.lock())?,
+                MapFormat::Ply => map.write_ply(stdout.lock())?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    if let Some(command) = &args.command {
+        return run_command(command);
+    }
+    let path = args.input.as_deref().context("missing puzzle input")?;
+
+    if let Some(part) = args.part.filter(|part| !(1..=2).contains(part)) {
+        bail!("no part {}, expected 1 or 2", part);
+    }
+
+    let solutions = registry::SOLUTIONS
+        .iter()
+        .filter(|solution| args.day.is_none_or(|day| solution.day == day))
+        .filter(|solution| args.part.is_none_or(|part| solution.part == part))
+        .collect::<Vec<&Solution>>();
+
+    match args.day {
+        Some(day) if solutions.is_empty() => bail!("no solution for day {}", day),
+        None if !args.params.is_empty() => bail!("--param needs a --day"),
+        _ => {}
+    }
+
+    if args.record.is_some() && !args.params.is_empty() {
+        bail!("--record cannot be combined with --param");
+    }
+
+    let inputs = read_inputs(&args, path, &solutions)?;
+
+    if let Some(format) = args.report {
+        if args.record.is_some() {
+            bail!("--record cannot be combined with --report");
+        }
+
+        let mut report = Report::default();
+        for solution in solutions {
+            let outcome = solution.measure(&inputs[&solution.day], &args.params)?;
+            report.push(report::Entry::new(solution, outcome));
+        }
+
+        let stdout = io::stdout();
+        return match format {
+            Format::Json => report.write_json(stdout.lock()),
+            Format::Csv => report.write_csv(stdout.lock()),
+        };
+    }
+
+    let mut manifest = match &args.record {
+        Some(path) if path.exists() => Some(Manifest::load(path)?),
+        Some(_) => Some(Manifest::default()),
+        None => None,
+    };
+
+    for solution in solutions {
+        let answer = solution
+            .run_with(&inputs[&solution.day], &args.params)
+            .wrap_err_with(|| format!("day {} part {} failed", solution.day, solution.part))?;
+        match args.day {
+            Some(_) => println!("{}", answer),
+            None => println!("day {} part {}: {}", solution.day, solution.part, answer),
+        }
+
+        if let Some(manifest) = manifest.as_mut() {
+            manifest.record(solution.day, solution.part, &answer)?;
+        }
+    }
+
+    if let (Some(manifest), Some(path)) = (manifest, &args.record) {
+        manifest.save(path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Tunable puzzle constants, so variants of a puzzle can be run without
+//! editing its solution.
+
+use eyre::{bail, ContextCompat};
+
+/// The tunable constants of a single day. [`Default`] gives the values from
+/// the puzzle text.
+pub trait Params: Default {
+    /// The name of every parameter, in declaration order.
+    const NAMES: &'static [&'static str];
+
+    /// Overrides the parameter named `key` with the text `value`.
+    fn set(&mut self, key: &str, value: &str) -> eyre::Result<()>;
+}
+
+/// A single `key=value` parameter override.
+pub type Override = (String, String);
+
+/// Splits a `key=value` override.
+pub fn parse_override(s: &str) -> eyre::Result<Override> {
+    let (key, value) = s
+        .split_once('=')
+        .with_context(|| format!("expected key=value, found {:?}", s))?;
+    if key.is_empty() {
+        bail!("missing parameter name in {:?}", s);
+    }
+
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Applies each override in turn over the defaults.
+pub fn with_overrides<P: Params>(overrides: &[Override]) -> eyre::Result<P> {
+    overrides
+        .iter()
+        .try_fold(P::default(), |mut params, (key, value)| {
+            params.set(key, value)?;
+            Ok(params)
+        })
+}
+
+/// Declares a day's `Params` struct along with its defaults.
+macro_rules! define_params {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident: $ty:ty = $default:expr,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+        }
+
+        impl $crate::params::Params for $name {
+            const NAMES: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn set(&mut self, key: &str, value: &str) -> eyre::Result<()> {
+                match key {
+                    $(
+                        stringify!($field) => {
+                            self.$field = value.parse().map_err(|e| {
+                                eyre::eyre!("invalid value {:?} for {}: {}", value, key, e)
+                            })?;
+                        }
+                    )*
+                    _ => eyre::bail!(
+                        "unknown parameter {:?}, expected one of: {}",
+                        key,
+                        <Self as $crate::params::Params>::NAMES.join(", ")
+                    ),
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+pub(crate) use define_params;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    define_params! {
+        pub struct Example {
+            pub rounds: usize = 10,
+            pub region: i64 = 50,
+        }
+    }
+
+    #[test]
+    fn test_with_overrides() -> eyre::Result<()> {
+        let params: Example = with_overrides(&[parse_override("region=-3")?])?;
+        assert_eq!(
+            params,
+            Example {
+                rounds: 10,
+                region: -3
+            }
+        );
+
+        assert!(with_overrides::<Example>(&[parse_override("rounds=x")?]).is_err());
+
+        let error = with_overrides::<Example>(&[parse_override("steps=1")?]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown parameter \"steps\", expected one of: rounds, region"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_override() {
+        assert!(parse_override("rounds").is_err());
+        assert!(parse_override("=5").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+//! A registry of every day's generator and solvers, for running puzzles
+//! without going through `cargo aoc`.
+
+use crate::params::{self, Override};
+use serde::Serialize;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// A single puzzle part that can be run against raw puzzle input.
+pub struct Solution {
+    pub day: u8,
+    pub part: u8,
+    measure: fn(&str, &[Override]) -> eyre::Result<Outcome>,
+}
+
+impl Solution {
+    /// Parses `input` with the day's generator and runs the part's solver on
+    /// it.
+    pub fn run(&self, input: &str) -> eyre::Result<String> {
+        self.run_with(input, &[])
+    }
+
+    /// Runs the part with some of the day's parameters overridden. Fails if
+    /// the day has no parameter with one of the given names.
+    pub fn run_with(&self, input: &str, overrides: &[Override]) -> eyre::Result<String> {
+        self.measure(input, overrides)?.answer.into_result()
+    }
+
+    /// Runs the part like [`Solution::run_with`], timing the generator and
+    /// the solver. Only invalid overrides are an error, a failing generator
+    /// or solver is recorded in the outcome's answer.
+    pub fn measure(&self, input: &str, overrides: &[Override]) -> eyre::Result<Outcome> {
+        (self.measure)(input, overrides)
+    }
+}
+
+/// The answer from a single run of a solution and how long it took.
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub answer: Answer,
+    pub generator: Duration,
+    /// The time spent in the solver, or `None` if the generator failed.
+    pub solver: Option<Duration>,
+}
+
+fn measure<I, E: Into<eyre::Report>, A: IntoAnswer>(
+    input: &str,
+    generator: impl FnOnce(&str) -> Result<I, E>,
+    solver: impl FnOnce(&I) -> A,
+) -> Outcome {
+    let start = Instant::now();
+    let parsed = generator(input);
+    let generator = start.elapsed();
+
+    match parsed {
+        Ok(input) => {
+            let start = Instant::now();
+            let answer = solver(&input).into_answer();
+            Outcome {
+                answer,
+                generator,
+                solver: Some(start.elapsed()),
+            }
+        }
+        Err(error) => Outcome {
+            answer: Answer::Error(format!("{:#}", error.into())),
+            generator,
+            solver: None,
+        },
+    }
+}
+
+/// A solver's answer in a common form, with a missing answer and an error
+/// kept as values rather than failing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "answer", rename_all = "snake_case")]
+pub enum Answer {
+    Unsigned(u128),
+    Signed(i128),
+    /// Answers that aren't numbers, such as the letters drawn by day 13.
+    Text(String),
+    /// The solver ran but found no answer.
+    None,
+    /// The generator or the solver failed, with the error's full chain.
+    Error(String),
+}
+
+impl Answer {
+    /// The name the answer's variant is serialized with.
+    pub fn status(&self) -> &'static str {
+        match self {
+            Answer::Unsigned(_) => "unsigned",
+            Answer::Signed(_) => "signed",
+            Answer::Text(_) => "text",
+            Answer::None => "none",
+            Answer::Error(_) => "error",
+        }
+    }
+
+    /// Converts back into the printable answer, failing for a missing answer
+    /// or an error.
+    pub fn into_result(self) -> eyre::Result<String> {
+        match self {
+            Answer::None => Err(eyre::eyre!("no answer found")),
+            Answer::Error(message) => Err(eyre::eyre!(message)),
+            answer => Ok(answer.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Unsigned(answer) => write!(f, "{}", answer),
+            Answer::Signed(answer) => write!(f, "{}", answer),
+            Answer::Text(answer) | Answer::Error(answer) => write!(f, "{}", answer),
+            Answer::None => write!(f, "no answer found"),
+        }
+    }
+}
+
+/// Converts whatever a solver returns into an [`Answer`]. Days implement it
+/// for their own answer types.
+pub trait IntoAnswer {
+    fn into_answer(self) -> Answer;
+}
+
+impl<T: IntoAnswer> IntoAnswer for Option<T> {
+    fn into_answer(self) -> Answer {
+        self.map_or(Answer::None, IntoAnswer::into_answer)
+    }
+}
+
+impl<T: IntoAnswer> IntoAnswer for eyre::Result<T> {
+    fn into_answer(self) -> Answer {
+        self.map_or_else(
+            |error| Answer::Error(format!("{:#}", error)),
+            IntoAnswer::into_answer,
+        )
+    }
+}
+
+impl IntoAnswer for String {
+    fn into_answer(self) -> Answer {
+        Answer::Text(self)
+    }
+}
+
+macro_rules! impl_into_answer {
+    ($variant:ident: $($t:ty),*) => {
+        $(
+            impl IntoAnswer for $t {
+                fn into_answer(self) -> Answer {
+                    Answer::$variant(self as _)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_answer!(Unsigned: u16, u32, u64, u128, usize);
+impl_into_answer!(Signed: i16, i32, i64);
+
+macro_rules! solution {
+    ($day:literal, $part:literal, $module:ident, $solver:ident, parse $params:ident) => {
+        Solution {
+            day: $day,
+            part: $part,
+            measure: |input, overrides| {
+                let params = params::with_overrides::<crate::$module::$params>(overrides)?;
+                Ok(measure(
+                    input,
+                    |input| crate::$module::parse_with(input, &params),
+                    |input| crate::$module::$solver(input),
+                ))
+            },
+        }
+    };
+    ($day:literal, $part:literal, $module:ident, $solver:ident, ignore $params:ident) => {
+        Solution {
+            day: $day,
+            part: $part,
+            measure: |input, overrides| {
+                params::with_overrides::<crate::$module::$params>(overrides)?;
+                Ok(measure(input, crate::$module::parse, |input| {
+                    crate::$module::$solver(input)
+                }))
+            },
+        }
+    };
+    ($day:literal, $part:literal, $module:ident, $solver:ident) => {
+        Solution {
+            day: $day,
+            part: $part,
+            measure: |input, overrides| {
+                if let Some((key, _)) = overrides.first() {
+                    eyre::bail!("day {} has no parameter {:?}", $day, key);
+                }
+
+                Ok(measure(input, crate::$module::parse, |input| {
+                    crate::$module::$solver(input)
+                }))
+            },
+        }
+    };
+    ($day:literal, $part:literal, $module:ident, $default:ident, $params:ident, $solver:ident) => {
+        Solution {
+            day: $day,
+            part: $part,
+            measure: |input, overrides| {
+                let params = params::with_overrides::<crate::$module::$params>(overrides)?;
+                Ok(measure(input, crate::$module::parse, |input| {
+                    crate::$module::$solver(input, &params)
+                }))
+            },
+        }
+    };
+}
+
+macro_rules! registry {
+    (
+        $(
+            $day:literal => $module:ident
+            $(with $params:ident)?
+            $(part1 with $part1_params:ident)?
+            $(parsed with $parse_params:ident)?
+        ),* $(,)?
+    ) => {
+        /// Every solution, ordered by day and then part.
+        pub static SOLUTIONS: &[Solution] = &[
+            $(
+                solution!(
+                    $day, 1, $module, part1
+                    $(, $params, part1_with)?
+                    $(, $part1_params, part1_with)?
+                    $(, parse $parse_params)?
+                ),
+                solution!(
+                    $day, 2, $module, part2
+                    $(, $params, part2_with)?
+                    $(, ignore $part1_params)?
+                    $(, parse $parse_params)?
+                ),
+            )*
+        ];
+    };
+}
+
+registry! {
+    1 => day1,
+    2 => day2,
+    3 => day3,
+    4 => day4 parsed with BoardParams,
+    5 => day5,
+    6 => day6 with Params,
+    7 => day7,
+    8 => day8,
+    9 => day9,
+    10 => day10,
+    11 => day11 part1 with Params,
+    12 => day12,
+    13 => day13,
+    14 => day14 with Params,
+    15 => day15 with Params,
+    16 => day16,
+    17 => day17,
+    18 => day18,
+    19 => day19 parsed with MatchConfig,
+    20 => day20 with Params,
+    21 => day21 with Params,
+    22 => day22 part1 with Params,
+}
+
+/// Looks up the solution for a given day and part.
+pub fn find(day: u8, part: u8) -> Option<&'static Solution> {
+    SOLUTIONS
+        .iter()
+        .find(|solution| solution.day == day && solution.part == part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::ContextCompat;
+
+    fn param(key: &str, value: &str) -> Override {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_part1_params() -> eyre::Result<()> {
+        let day11 = find(11, 2).context("missing day 11 part 2")?;
+        assert_eq!(day11.run_with("9", &[param("steps", "10")])?, "1");
+
+        let error = day11.run_with("9", &[param("rounds", "10")]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown parameter \"rounds\", expected one of: steps"
+        );
+
+        Ok(())
+    }
+}
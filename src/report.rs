@@ -0,0 +1,167 @@
+//! Machine readable reports of every answer along with how long the
+//! generator and solver took, as JSON or CSV.
+
+use crate::registry::{Answer, Outcome, Solution};
+use eyre::WrapErr;
+use serde::Serialize;
+use std::io::Write;
+
+/// A single part's answer and timings.
+#[derive(Clone, Debug, Serialize)]
+pub struct Entry {
+    pub day: u8,
+    pub part: u8,
+    #[serde(flatten)]
+    pub answer: Answer,
+    pub generator_ns: u128,
+    /// The time spent in the solver, or `None` if the generator failed.
+    pub solver_ns: Option<u128>,
+}
+
+impl Entry {
+    pub fn new(solution: &Solution, outcome: Outcome) -> Self {
+        Self {
+            day: solution.day,
+            part: solution.part,
+            answer: outcome.answer,
+            generator_ns: outcome.generator.as_nanos(),
+            solver_ns: outcome.solver.map(|solver| solver.as_nanos()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    pub entries: Vec<Entry>,
+}
+
+impl Report {
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    pub fn write_json<W: Write>(&self, mut writer: W) -> eyre::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer).wrap_err("unable to write JSON report")
+    }
+
+    /// Writes one row per entry. Missing answers and solver times are left
+    /// empty, and errors are written in the answer column.
+    pub fn write_csv<W: Write>(&self, writer: W) -> eyre::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record([
+            "day",
+            "part",
+            "status",
+            "answer",
+            "generator_ns",
+            "solver_ns",
+        ])?;
+
+        for entry in &self.entries {
+            let answer = match &entry.answer {
+                Answer::None => String::new(),
+                answer => answer.to_string(),
+            };
+            let solver_ns = entry
+                .solver_ns
+                .map(|solver_ns| solver_ns.to_string())
+                .unwrap_or_default();
+
+            writer.write_record([
+                entry.day.to_string(),
+                entry.part.to_string(),
+                entry.answer.status().to_string(),
+                answer,
+                entry.generator_ns.to_string(),
+                solver_ns,
+            ])?;
+        }
+
+        writer.flush().wrap_err("unable to write CSV report")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let entries = vec![
+            Entry {
+                day: 13,
+                part: 2,
+                answer: Answer::Text("#..#\n#..#".to_string()),
+                generator_ns: 10,
+                solver_ns: Some(20),
+            },
+            Entry {
+                day: 17,
+                part: 1,
+                answer: Answer::None,
+                generator_ns: 30,
+                solver_ns: Some(40),
+            },
+            Entry {
+                day: 22,
+                part: 2,
+                answer: Answer::Error("day 22, line 1, column 1: bad".to_string()),
+                generator_ns: 50,
+                solver_ns: None,
+            },
+            Entry {
+                day: 22,
+                part: 1,
+                answer: Answer::Unsigned(u128::MAX),
+                generator_ns: 60,
+                solver_ns: Some(70),
+            },
+        ];
+
+        Report { entries }
+    }
+
+    #[test]
+    fn test_json() -> eyre::Result<()> {
+        let mut json = vec![];
+        report().write_json(&mut json)?;
+        let json: serde_json::Value = serde_json::from_slice(&json)?;
+
+        let entries = &json["entries"];
+        assert_eq!(
+            entries[0],
+            serde_json::json!({
+                "day": 13,
+                "part": 2,
+                "status": "text",
+                "answer": "#..#\n#..#",
+                "generator_ns": 10,
+                "solver_ns": 20,
+            })
+        );
+        assert_eq!(entries[1]["status"], "none");
+        assert!(entries[1].get("answer").is_none());
+        assert_eq!(entries[2]["status"], "error");
+        assert_eq!(entries[2]["solver_ns"], serde_json::Value::Null);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv() -> eyre::Result<()> {
+        let mut csv = vec![];
+        report().write_csv(&mut csv)?;
+
+        assert_eq!(
+            String::from_utf8(csv)?,
+            "day,part,status,answer,generator_ns,solver_ns
+13,2,text,\"#..#\n#..#\",10,20
+17,1,none,,30,40
+22,2,error,\"day 22, line 1, column 1: bad\",50,
+22,1,unsigned,340282366920938463463374607431768211455,60,70
+"
+        );
+
+        Ok(())
+    }
+}
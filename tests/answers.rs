@@ -0,0 +1,32 @@
+use advent_2021::{
+    answers::{Manifest, MANIFEST_PATH},
+    registry::SOLUTIONS,
+};
+use std::fs;
+
+#[test]
+fn test_recorded_answers() -> eyre::Result<()> {
+    let manifest = Manifest::load(MANIFEST_PATH)?;
+
+    let mut failures = vec![];
+    for solution in SOLUTIONS {
+        let (day, part) = (solution.day, solution.part);
+        let input = fs::read_to_string(format!("input/2021/day{}.txt", day))?;
+
+        match (solution.run(&input), manifest.get(day, part)) {
+            (Ok(actual), Some(expected)) if actual == expected => {}
+            (Ok(actual), Some(expected)) => failures.push(format!(
+                "day {} part {}: expected {:?}, got {:?}",
+                day, part, expected, actual
+            )),
+            (Ok(actual), None) => eprintln!(
+                "day {} part {}: no recorded answer, got {:?}",
+                day, part, actual
+            ),
+            (Err(error), _) => failures.push(format!("day {} part {}: {}", day, part, error)),
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! Feeds arbitrary and almost-valid input to every generator and solver.
+//! Malformed input has to come back as an error rather than a panic.
+
+use advent_2021::registry::SOLUTIONS;
+use proptest::prelude::*;
+
+fn run_day(day: u8, input: &str) {
+    for solution in SOLUTIONS.iter().filter(|solution| solution.day == day) {
+        let _ = solution.run(input);
+    }
+}
+
+/// A transmission of sum packets nested `depth` deep around a literal, each
+/// giving its sub-packet count.
+fn nested_day16(depth: usize) -> String {
+    let mut bits = "000000100000000001".repeat(depth);
+    bits.push_str("00010000001");
+    bits.push_str(&"0".repeat(bits.len().next_multiple_of(8) - bits.len()));
+
+    bits.as_bytes()
+        .chunks(8)
+        .map(|chunk| {
+            let byte = chunk.iter().fold(0, |acc, &bit| acc << 1 | (bit - b'0'));
+            format!("{:02X}", byte)
+        })
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_arbitrary_input(input in any::<String>()) {
+        for solution in SOLUTIONS {
+            let _ = solution.run(&input);
+        }
+    }
+
+    #[test]
+    fn test_day1(input in "([0-9]{1,10}\n){0,10}") {
+        run_day(1, &input);
+    }
+
+    #[test]
+    fn test_day2(input in "((forward|down|up) [0-9]{1,10}\n){0,10}") {
+        run_day(2, &input);
+    }
+
+    #[test]
+    fn test_day3(input in "([01]{0,4}\n){0,8}") {
+        run_day(3, &input);
+    }
+
+    #[test]
+    fn test_day4(input in "[0-9]{1,2}(,[0-9]{1,2}){0,30}(\n\n([0-9]{1,2}( [0-9]{1,2}){4}\n){4}[0-9]{1,2}( [0-9]{1,2}){4}){0,3}") {
+        run_day(4, &input);
+    }
+
+    #[test]
+    fn test_day5(input in "([0-9]{1,2},[0-9]{1,2} -> [0-9]{1,2},[0-9]{1,2}\n){0,10}") {
+        run_day(5, &input);
+    }
+
+    #[test]
+    fn test_day6(input in "[0-9]{1,2}(,[0-9]{1,2}){0,20}") {
+        run_day(6, &input);
+    }
+
+    #[test]
+    fn test_day7(input in "-?[0-9]{1,3}(,-?[0-9]{1,3}){0,20}") {
+        run_day(7, &input);
+    }
+
+    #[test]
+    fn test_day8(input in "(([a-g]{1,7} ){9}[a-g]{1,7} \\| [a-g]{1,7}( [a-g]{1,7}){3}\n){0,5}") {
+        run_day(8, &input);
+    }
+
+    #[test]
+    fn test_day9(input in "([0-9]{4}\n){0,4}") {
+        run_day(9, &input);
+    }
+
+    #[test]
+    fn test_day10(input in "([()\\[\\]{}<>]{0,40}\n){0,5}") {
+        run_day(10, &input);
+    }
+
+    #[test]
+    fn test_day11(input in "([0-9]{4}\n){0,4}") {
+        run_day(11, &input);
+    }
+
+    #[test]
+    fn test_day12(input in "((start|end|[a-c]|[A-B])-(start|end|[a-c]|[A-B])\n){0,8}") {
+        run_day(12, &input);
+    }
+
+    #[test]
+    fn test_day13(input in "([0-9]{1,2},[0-9]{1,2}\n){0,10}\n(fold along (x|y)=[0-9]{1,2}\n){0,3}") {
+        run_day(13, &input);
+    }
+
+    #[test]
+    fn test_day14(input in "[A-C]{0,10}\n\n([A-C]{2} -> [A-C]\n){0,9}") {
+        run_day(14, &input);
+    }
+
+    #[test]
+    fn test_day15(input in "([0-9]{4}\n){0,4}") {
+        run_day(15, &input);
+    }
+
+    #[test]
+    fn test_day16(input in "[0-9A-F]{0,40}") {
+        run_day(16, &input);
+    }
+
+    #[test]
+    fn test_day16_nested(depth in 0usize..50_000) {
+        run_day(16, &nested_day16(depth));
+    }
+
+    #[test]
+    fn test_day17(input in "target area: x=[0-9]{1,2}\\.\\.[0-9]{1,2}, y=-?[0-9]{1,2}\\.\\.-?[0-9]{1,2}") {
+        run_day(17, &input);
+    }
+
+    #[test]
+    fn test_day18(input in "([\\[\\],0-9]{0,30}\n){0,4}") {
+        run_day(18, &input);
+    }
+
+    #[test]
+    fn test_day19(input in "(--- scanner [0-9] ---\n(-?[0-9]{1,3},-?[0-9]{1,3},-?[0-9]{1,3}\n){0,5}\n){0,3}") {
+        run_day(19, &input);
+    }
+
+    #[test]
+    fn test_day20(input in "[#.]{512}\n\n([#.]{3}\n){0,3}") {
+        run_day(20, &input);
+    }
+
+    #[test]
+    fn test_day21(input in "(Player [12] starting position: [0-9]{1,2}\n){0,3}") {
+        run_day(21, &input);
+    }
+
+    #[test]
+    fn test_day22(input in "((on|off) x=-?[0-9]\\.\\.-?[0-9],y=-?[0-9]\\.\\.-?[0-9],z=-?[0-9]\\.\\.-?[0-9]\n){0,5}") {
+        run_day(22, &input);
+    }
+}